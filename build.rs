@@ -0,0 +1,19 @@
+// Generate the C header into OUT_DIR when building with the `capi` feature; a test checks that
+// the committed include/chord_script.h matches it
+
+fn main() {
+    #[cfg(feature = "capi")]
+    {
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+        let out_dir = std::env::var("OUT_DIR").expect("set by cargo");
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("valid cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_crate(&crate_dir)
+            .with_config(config)
+            .generate()
+            .expect("C header generation failed")
+            .write_to_file(format!("{}/chord_script.h", out_dir));
+    }
+}
@@ -0,0 +1,59 @@
+from typing import Literal, Optional
+
+Style = Literal["normal", "bold", "italic", "bold_italic"]
+Level = Literal["header1", "header2", "header3", "text", "chords"]
+
+class ParseError(ValueError):
+    help: str
+    start: int
+    """Character offset into the source where the error starts, as a string index"""
+    end: int
+    """Character offset where the error ends"""
+    line: int
+    column: int
+
+class TextSpan:
+    text: str
+    style: Style
+
+class Line:
+    level: Level
+    left: list[TextSpan]
+    center: list[TextSpan]
+    right: list[TextSpan]
+    chords: list[str]
+    """Chord line items as written, e.g. ["Am_G", "%", "(F E) 2x"]"""
+
+class Chart:
+    lines: list[Line]
+    title: Optional[str]
+    subtitle: Optional[str]
+
+def parse_chart(source: str) -> Chart: ...
+
+class SvgGenerator:
+    def __init__(
+        self,
+        *,
+        width: float = ...,
+        height: float = ...,
+        margin_horizontal: float = ...,
+        margin_vertical: float = ...,
+        font_family: str = ...,
+        bars_per_row: int = ...,
+        chord_symbols: Literal["spelled", "jazz"] = ...,
+        slash_notation: bool = ...,
+        header1_size: float = ...,
+        header1_weight: str | int = ...,
+        header1_line_height: float = ...,
+        header2_size: float = ...,
+        header2_weight: str | int = ...,
+        header2_line_height: float = ...,
+        header3_size: float = ...,
+        header3_weight: str | int = ...,
+        header3_line_height: float = ...,
+        text_size: float = ...,
+        text_weight: str | int = ...,
+        text_line_height: float = ...,
+    ) -> None: ...
+    def render(self, chart: Chart) -> str: ...
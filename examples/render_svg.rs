@@ -4,47 +4,27 @@ use chord_script::render::SvgGenerator;
 fn main() {
     // Create a sample chart
     let chart = Chart::new(vec![
-        Line {
-            level: LineLevel::Header1,
-            left: vec![],
-            center: vec![TextSpan::plain("My Song Title")],
-            right: vec![],
-        },
-        Line {
-            level: LineLevel::Header2,
-            left: vec![TextSpan::plain("Header 2")],
-            center: vec![],
-            right: vec![],
-        },
-        Line {
-            level: LineLevel::Header3,
-            left: vec![TextSpan::new("Verse 1", TextStyle::Italic)],
-            center: vec![],
-            right: vec![],
-        },
-        Line {
-            level: LineLevel::Text,
-            left: vec![
+        Line::new(LineLevel::Header1, vec![], vec![TextSpan::plain("My Song Title")], vec![]),
+        Line::new(LineLevel::Header2, vec![TextSpan::plain("Header 2")], vec![], vec![]),
+        Line::new(
+            LineLevel::Header3,
+            vec![TextSpan::new("Verse 1", TextStyle::Italic)],
+            vec![],
+            vec![],
+        ),
+        Line::new(
+            LineLevel::Text,
+            vec![
                 TextSpan::plain("This is "),
                 TextSpan::new("some", TextStyle::Bold),
                 TextSpan::plain(" text with "),
                 TextSpan::new("styling", TextStyle::Italic),
             ],
-            center: vec![],
-            right: vec![],
-        },
-        Line {
-            level: LineLevel::Text,
-            left: vec![],
-            center: vec![TextSpan::plain("Centered text")],
-            right: vec![],
-        },
-        Line {
-            level: LineLevel::Text,
-            left: vec![],
-            center: vec![],
-            right: vec![TextSpan::plain("Right aligned")],
-        },
+            vec![],
+            vec![],
+        ),
+        Line::new(LineLevel::Text, vec![], vec![TextSpan::plain("Centered text")], vec![]),
+        Line::new(LineLevel::Text, vec![], vec![], vec![TextSpan::plain("Right aligned")]),
     ]);
 
     // Generate SVG
@@ -0,0 +1,77 @@
+#ifndef CHORD_SCRIPT_H
+#define CHORD_SCRIPT_H
+
+/* Generated by cbindgen from src/capi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A parsed chart (opaque)
+typedef struct ChordScriptChart ChordScriptChart;
+
+// Why a call failed, with the location in the source for parse errors
+typedef struct ChordScriptError {
+  // NUL-terminated description, owned by the error
+  char *message;
+  // Byte offset where the offending input starts (0 when not a parse error)
+  size_t start;
+  // Byte offset where the offending input ends
+  size_t end;
+  // One-based line of `start` (0 when not a parse error)
+  size_t line;
+  // One-based column of `start`, in characters
+  size_t column;
+} ChordScriptError;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parse NUL-terminated UTF-8 chart source.
+//
+// Returns NULL on failure; if `error` is not NULL it then receives a new error object.
+//
+// # Safety
+//
+// `source` must be NULL or a valid NUL-terminated string, and `error` NULL or writable.
+struct ChordScriptChart *chord_script_parse(const char *source, struct ChordScriptError **error);
+
+// Render a chart to a NUL-terminated SVG document, to be freed with `chord_script_string_free`.
+//
+// Returns NULL on failure; if `error` is not NULL it then receives a new error object.
+//
+// # Safety
+//
+// `chart` must be NULL or a chart returned by `chord_script_parse`, and `error` NULL or writable.
+char *chord_script_render_svg(const struct ChordScriptChart *chart,
+                              struct ChordScriptError **error);
+
+// Release a chart returned by `chord_script_parse`.
+//
+// # Safety
+//
+// `chart` must be NULL or a chart from this API that has not been freed yet.
+void chord_script_chart_free(struct ChordScriptChart *chart);
+
+// Release a string returned by this API.
+//
+// # Safety
+//
+// `string` must be NULL or a string from this API that has not been freed yet.
+void chord_script_string_free(char *string);
+
+// Release an error, including its message.
+//
+// # Safety
+//
+// `error` must be NULL or an error from this API that has not been freed yet.
+void chord_script_error_free(struct ChordScriptError *error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHORD_SCRIPT_H */
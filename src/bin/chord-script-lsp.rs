@@ -0,0 +1,8 @@
+use std::process;
+
+fn main() {
+    if let Err(err) = chord_script::lsp::run() {
+        eprintln!("chord-script-lsp: {}", err);
+        process::exit(1);
+    }
+}
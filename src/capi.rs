@@ -0,0 +1,227 @@
+// C API for embedding chord-script in other applications
+//
+// Build with `--features capi`; the header `include/chord_script.h` is regenerated by the
+// build script. Every pointer returned by this API is owned by the caller and must be released
+// with the matching `chord_script_*_free` function. Passing NULL to a free function is a no-op.
+
+use crate::model::Chart;
+use crate::parser;
+use crate::render::SvgGenerator;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// A parsed chart (opaque)
+pub struct ChordScriptChart {
+    chart: Chart,
+}
+
+/// Why a call failed, with the location in the source for parse errors
+#[repr(C)]
+pub struct ChordScriptError {
+    /// NUL-terminated description, owned by the error
+    pub message: *mut c_char,
+    /// Byte offset where the offending input starts (0 when not a parse error)
+    pub start: usize,
+    /// Byte offset where the offending input ends
+    pub end: usize,
+    /// One-based line of `start` (0 when not a parse error)
+    pub line: usize,
+    /// One-based column of `start`, in characters
+    pub column: usize,
+}
+
+impl ChordScriptError {
+    fn new(message: &str) -> Self {
+        Self {
+            message: to_c_string(message),
+            start: 0,
+            end: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    fn parse(error: &parser::ParseError) -> Self {
+        let span = error.span();
+        let (line, column) = error.line_col();
+        Self {
+            message: to_c_string(error.help()),
+            start: span.start,
+            end: span.end,
+            line,
+            column,
+        }
+    }
+}
+
+/// Parse NUL-terminated UTF-8 chart source.
+///
+/// Returns NULL on failure; if `error` is not NULL it then receives a new error object.
+///
+/// # Safety
+///
+/// `source` must be NULL or a valid NUL-terminated string, and `error` NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn chord_script_parse(
+    source: *const c_char,
+    error: *mut *mut ChordScriptError,
+) -> *mut ChordScriptChart {
+    let result = guard(|| {
+        if source.is_null() {
+            return Err(ChordScriptError::new("source is NULL"));
+        }
+        let source = CStr::from_ptr(source)
+            .to_str()
+            .map_err(|_| ChordScriptError::new("source is not valid UTF-8"))?;
+        parser::parse_chart(source)
+            .map(|chart| ChordScriptChart { chart })
+            .map_err(|parse_error| ChordScriptError::parse(&parse_error))
+    });
+    finish(result, error)
+}
+
+/// Render a chart to a NUL-terminated SVG document, to be freed with `chord_script_string_free`.
+///
+/// Returns NULL on failure; if `error` is not NULL it then receives a new error object.
+///
+/// # Safety
+///
+/// `chart` must be NULL or a chart returned by `chord_script_parse`, and `error` NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn chord_script_render_svg(
+    chart: *const ChordScriptChart,
+    error: *mut *mut ChordScriptError,
+) -> *mut c_char {
+    let result = guard(|| match chart.as_ref() {
+        Some(chart) => Ok(to_c_string(&SvgGenerator::with_defaults().render(&chart.chart))),
+        None => Err(ChordScriptError::new("chart is NULL")),
+    });
+    match result {
+        Ok(svg) => svg,
+        Err(failure) => {
+            set_error(error, failure);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a chart returned by `chord_script_parse`.
+///
+/// # Safety
+///
+/// `chart` must be NULL or a chart from this API that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chord_script_chart_free(chart: *mut ChordScriptChart) {
+    if !chart.is_null() {
+        drop(Box::from_raw(chart));
+    }
+}
+
+/// Release a string returned by this API.
+///
+/// # Safety
+///
+/// `string` must be NULL or a string from this API that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chord_script_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Release an error, including its message.
+///
+/// # Safety
+///
+/// `error` must be NULL or an error from this API that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chord_script_error_free(error: *mut ChordScriptError) {
+    if !error.is_null() {
+        let error = Box::from_raw(error);
+        chord_script_string_free(error.message);
+    }
+}
+
+/// Run `body`, turning a panic into an error so it never unwinds into C
+fn guard<T>(body: impl FnOnce() -> Result<T, ChordScriptError>) -> Result<T, ChordScriptError> {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| Err(ChordScriptError::new("internal error")))
+}
+
+/// Box a successful result for C, or report the error and return NULL
+unsafe fn finish<T>(result: Result<T, ChordScriptError>, error: *mut *mut ChordScriptError) -> *mut T {
+    match result {
+        Ok(value) => Box::into_raw(Box::new(value)),
+        Err(failure) => {
+            set_error(error, failure);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn set_error(error: *mut *mut ChordScriptError, failure: ChordScriptError) {
+    match error.as_mut() {
+        Some(slot) => *slot = Box::into_raw(Box::new(failure)),
+        None => chord_script_string_free(failure.message),
+    }
+}
+
+/// Copy into a C string; interior NULs cannot occur in charts but are replaced to be safe
+fn to_c_string(text: &str) -> *mut c_char {
+    CString::new(text.replace('\0', "\u{FFFD}"))
+        .expect("NULs were replaced")
+        .into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render() {
+        let source = CString::new("=== <>Song\nAm G\n").unwrap();
+        unsafe {
+            let mut error = ptr::null_mut();
+            let chart = chord_script_parse(source.as_ptr(), &mut error);
+            assert!(!chart.is_null());
+            assert!(error.is_null());
+
+            let svg = chord_script_render_svg(chart, ptr::null_mut());
+            assert!(CStr::from_ptr(svg).to_str().unwrap().starts_with("<svg"));
+            chord_script_string_free(svg);
+            chord_script_chart_free(chart);
+        }
+    }
+
+    #[test]
+    fn test_errors_carry_location() {
+        let source = CString::new("= <>Song\n(Am G\n").unwrap();
+        let expected = parser::parse_chart("= <>Song\n(Am G\n").unwrap_err();
+        unsafe {
+            let mut error = ptr::null_mut();
+            assert!(chord_script_parse(source.as_ptr(), &mut error).is_null());
+            let report = &*error;
+            assert_eq!(CStr::from_ptr(report.message).to_str().unwrap(), expected.help());
+            assert_eq!(report.start..report.end, expected.span());
+            assert_eq!((report.line, report.column), expected.line_col());
+            chord_script_error_free(error);
+
+            // Without an error slot the failure is only signalled by NULL
+            assert!(chord_script_parse(ptr::null(), ptr::null_mut()).is_null());
+            assert!(chord_script_render_svg(ptr::null(), &mut error).is_null());
+            assert_eq!(CStr::from_ptr((*error).message).to_str().unwrap(), "chart is NULL");
+            chord_script_error_free(error);
+        }
+    }
+
+    #[test]
+    fn test_committed_header_is_up_to_date() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/chord_script.h"));
+        let committed = include_str!("../include/chord_script.h");
+        assert!(
+            generated == committed,
+            "include/chord_script.h is stale; copy the header from {}",
+            env!("OUT_DIR")
+        );
+    }
+}
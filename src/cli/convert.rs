@@ -0,0 +1,118 @@
+// Convert a chart to another chart, notation or data format
+
+use super::{read_chart, write_file, CliError, Result};
+use chord_script::export::{chordpro, ireal, latex, markdown, midi, musicxml};
+use chord_script::model::Chart;
+use clap::ValueEnum;
+use std::io::Write;
+use std::path::Path;
+
+/// Target format for `convert`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Target {
+    /// Versioned JSON document of the chart model (needs the `serde` feature)
+    Json,
+    /// The same document as YAML (needs the `serde` feature)
+    Yaml,
+    Chordpro,
+    Markdown,
+    Latex,
+    Musicxml,
+    /// Standard MIDI file with block-chord playback
+    Midi,
+    /// iReal Pro song URL
+    Ireal,
+}
+
+impl Target {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Target::Json => "json",
+            Target::Yaml => "yaml",
+            Target::Chordpro => "cho",
+            Target::Markdown => "md",
+            Target::Latex => "tex",
+            Target::Musicxml => "musicxml",
+            Target::Midi => "mid",
+            Target::Ireal => "txt",
+        }
+    }
+}
+
+/// Convert one chart file; `output` of `-` writes to stdout
+pub fn convert(input: &Path, target: Target, output: Option<&Path>) -> Result<()> {
+    let chart = read_chart(input)?;
+    let bytes = export(&chart, target)?;
+
+    match output {
+        Some(path) if path == Path::new("-") => {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(&bytes)
+                .and_then(|()| stdout.flush())
+                .map_err(|source| CliError::Write {
+                    path: path.to_path_buf(),
+                    source,
+                })
+        }
+        output => {
+            let path = output
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| input.with_extension(target.extension()));
+            write_file(&path, &bytes)?;
+            println!("✓ {} → {}", input.display(), path.display());
+            Ok(())
+        }
+    }
+}
+
+fn export(chart: &Chart, target: Target) -> Result<Vec<u8>> {
+    let text = match target {
+        Target::Json | Target::Yaml => return serialize(chart, target),
+        Target::Chordpro => chordpro::export(chart),
+        Target::Markdown => markdown::export(chart),
+        Target::Latex => latex::export(chart),
+        Target::Musicxml => musicxml::export(chart),
+        Target::Midi => return Ok(midi::export(chart)),
+        Target::Ireal => {
+            if let Some(time) = chart.time_signature().filter(|&time| ireal::time_code(time).is_none()) {
+                eprintln!("⚠ iReal Pro has no {} time signature; the song is written in 4/4", time);
+            }
+            format!("{}\n", ireal::export(chart, ireal::UrlFormat::IRealB))
+        }
+    };
+    Ok(text.into_bytes())
+}
+
+#[cfg(feature = "serde")]
+fn serialize(chart: &Chart, target: Target) -> Result<Vec<u8>> {
+    let text = match target {
+        Target::Yaml => chart.to_yaml()?,
+        _ => format!("{}\n", chart.to_json()?),
+    };
+    Ok(text.into_bytes())
+}
+
+#[cfg(not(feature = "serde"))]
+fn serialize(_chart: &Chart, target: Target) -> Result<Vec<u8>> {
+    Err(CliError::Unsupported(format!(
+        "{} output requires building chord-script with the `serde` feature",
+        target.extension().to_uppercase()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chord_script::parser::parse_chart;
+
+    #[test]
+    fn test_export_targets() {
+        let chart = parse_chart("=== <>Song\nC G\n").unwrap();
+
+        let chordpro = export(&chart, Target::Chordpro).unwrap();
+        assert!(String::from_utf8(chordpro).unwrap().starts_with("{title: Song}"));
+        assert!(export(&chart, Target::Midi).unwrap().starts_with(b"MThd"));
+        assert_eq!(export(&chart, Target::Json).is_ok(), cfg!(feature = "serde"));
+    }
+}
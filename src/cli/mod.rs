@@ -0,0 +1,213 @@
+// Command-line interface for chord-script
+
+mod convert;
+mod render;
+mod serve;
+mod setlist;
+mod watch;
+
+use chord_script::model::Chart;
+#[cfg(feature = "serde")]
+use chord_script::model::DocumentError;
+use chord_script::parser::{parse_chart, ParseError};
+use chord_script::render::{RenderError, SvgGenerator};
+use clap::{Parser, Subcommand};
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// CLI error type
+#[derive(Debug, Error, Diagnostic)]
+pub enum CliError {
+    #[error("failed to read '{}'", path.display())]
+    #[diagnostic(code(cli::read_error))]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("failed to parse '{}'", path.display())]
+    #[diagnostic(code(cli::parse_error))]
+    Parse {
+        path: PathBuf,
+        #[source]
+        #[diagnostic_source]
+        source: ParseError,
+    },
+
+    #[error("failed to write '{}'", path.display())]
+    #[diagnostic(code(cli::write_error))]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{count} chart(s) failed to render")]
+    #[diagnostic(code(cli::render_failed))]
+    RenderFailed { count: usize },
+
+    #[error("{0}")]
+    #[diagnostic(code(cli::unsupported))]
+    Unsupported(String),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Render(#[from] RenderError),
+
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Document(#[from] DocumentError),
+
+    #[error("failed to start the preview server on port {port}")]
+    #[diagnostic(code(cli::serve_error))]
+    Serve {
+        port: u16,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl CliError {
+    /// One-line description, including the source location for parse errors
+    pub fn summary(&self) -> String {
+        match self {
+            CliError::Read { source, .. } | CliError::Write { source, .. } => {
+                format!("{}: {}", self, source)
+            }
+            CliError::Parse { source, .. } => {
+                let (line, column) = source.line_col();
+                format!("{}:{}: {}", line, column, source.help())
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Result type alias for CLI operations
+pub type Result<T> = std::result::Result<T, CliError>;
+
+#[derive(Debug, Parser)]
+#[command(name = "chord-script", version, about = "Clean, chord-first charts focused on form and structure")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Preview a chart in the browser, re-rendering whenever the file changes
+    Serve {
+        /// Chart file to watch
+        file: PathBuf,
+        /// Port to listen on (localhost only)
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Render chart files (or directories of .chords files) to SVG, PDF, HTML or plain text
+    Render {
+        /// Chart files or directories
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Output format
+        #[arg(short, long, default_value = "svg", value_parser = render::FORMATS)]
+        format: String,
+        /// Directory for rendered files (defaults to next to each input)
+        #[arg(short, long)]
+        out_dir: Option<PathBuf>,
+        /// Keep running and re-render charts as they change
+        #[arg(short, long)]
+        watch: bool,
+    },
+    /// Convert a chart to JSON, YAML, ChordPro, Markdown, LaTeX, MusicXML, MIDI or an iReal Pro URL
+    Convert {
+        /// Chart file
+        input: PathBuf,
+        /// Target format
+        #[arg(short, long, value_enum)]
+        to: convert::Target,
+        /// Output file (defaults to next to the input; `-` for stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Compile a setlist manifest into one document with a table of contents
+    Setlist {
+        /// Setlist manifest listing chart paths
+        manifest: PathBuf,
+        /// Output format: one multi-page PDF, HTML or text file, or one numbered SVG per page
+        #[arg(short, long, default_value = "pdf", value_parser = render::FORMATS)]
+        format: String,
+        /// Output file for PDF, HTML or text, or directory for SVG pages (defaults to next to the manifest)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    pub fn run(self) -> Result<()> {
+        match self.command {
+            Command::Serve { file, port } => serve::serve(&file, port),
+            Command::Render {
+                inputs,
+                format,
+                out_dir,
+                watch,
+            } => render::render(
+                &render::RenderOptions {
+                    inputs,
+                    format,
+                    out_dir,
+                },
+                watch,
+            ),
+            Command::Convert { input, to, output } => convert::convert(&input, to, output.as_deref()),
+            Command::Setlist {
+                manifest,
+                format,
+                output,
+            } => setlist::compile(&manifest, &format, output.as_deref()),
+        }
+    }
+}
+
+/// Read and parse a chart file
+fn read_chart(path: &Path) -> Result<Chart> {
+    let input = fs::read_to_string(path).map_err(|source| CliError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_chart(&input).map_err(|source| CliError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Write rendered output, creating parent directories as needed
+fn write_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    let write_error = |source| CliError::Write {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(write_error)?;
+    }
+    fs::write(path, bytes).map_err(write_error)
+}
+
+/// Read and parse a chart file, then render it to SVG
+fn render_file(path: &Path) -> Result<String> {
+    let chart = read_chart(path)?;
+    Ok(SvgGenerator::with_defaults().render(&chart))
+}
+
+/// Render an error report as plain text (no ANSI colors)
+fn report_to_string(error: &CliError) -> String {
+    let handler = GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor());
+    let mut output = String::new();
+    let _ = handler.render_report(&mut output, error);
+    output
+}
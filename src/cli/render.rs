@@ -0,0 +1,132 @@
+// Render chart files to SVG, PDF, HTML or plain text, optionally re-rendering on change
+
+use super::watch::{chart_files, Watcher};
+use super::{read_chart, write_file, CliError, Result};
+use chord_script::render::{Registry, Renderer};
+use std::path::{Path, PathBuf};
+
+/// Backend names accepted by `--format`; `pdf` is only registered with the `pdf` feature
+pub const FORMATS: [&str; 4] = ["svg", "pdf", "txt", "html"];
+
+/// Where and how to write rendered charts
+pub struct RenderOptions {
+    pub inputs: Vec<PathBuf>,
+    /// Name of the backend in the render registry
+    pub format: String,
+    pub out_dir: Option<PathBuf>,
+}
+
+/// The registered backend for a format name
+pub fn backend<'a>(registry: &'a Registry, format: &str) -> Result<&'a dyn Renderer> {
+    registry.get(format).ok_or_else(|| {
+        CliError::Unsupported(match format {
+            "pdf" => "PDF output requires building chord-script with the `pdf` feature".to_string(),
+            _ => format!("unknown output format '{}'", format),
+        })
+    })
+}
+
+/// Render every chart once, then (with `watch`) keep re-rendering the files that change
+pub fn render(options: &RenderOptions, watch: bool) -> Result<()> {
+    let registry = Registry::default();
+    let renderer = backend(&registry, &options.format)?;
+
+    let files = chart_files(&options.inputs);
+    let failed = render_batch(options, renderer, &files);
+
+    if !watch {
+        return match failed {
+            0 => Ok(()),
+            count => Err(CliError::RenderFailed { count }),
+        };
+    }
+
+    println!("Watching {} chart(s) for changes...", files.len());
+    let mut watcher = Watcher::new(options.inputs.clone());
+    loop {
+        let changed = watcher.wait_for_changes();
+        render_batch(options, renderer, &changed);
+    }
+}
+
+/// Render files, printing one status line each; returns the number of failures
+fn render_batch(options: &RenderOptions, renderer: &dyn Renderer, files: &[PathBuf]) -> usize {
+    let mut failed = 0;
+    for file in files {
+        let output = output_path(file, &options.inputs, options.out_dir.as_deref(), renderer.extension());
+        match render_to(file, &output, renderer) {
+            Ok(()) => println!("✓ {} → {}", file.display(), output.display()),
+            Err(error) => {
+                failed += 1;
+                eprintln!("✗ {}: {}", file.display(), error.summary());
+            }
+        }
+    }
+    failed
+}
+
+fn render_to(file: &Path, output: &Path, renderer: &dyn Renderer) -> Result<()> {
+    let chart = read_chart(file)?;
+    let bytes = renderer.render(&chart)?;
+    write_assets(output, renderer)?;
+    write_file(output, &bytes)
+}
+
+/// The files a backend's documents refer to, next to `output`. Existing files are left alone,
+/// so re-renders don't rewrite them and edited copies are kept.
+pub fn write_assets(output: &Path, renderer: &dyn Renderer) -> Result<()> {
+    for (name, contents) in renderer.assets() {
+        let path = output.with_file_name(name);
+        if !path.exists() {
+            write_file(&path, &contents)?;
+        }
+    }
+    Ok(())
+}
+
+/// Output path for a chart: next to the input, or mirrored under `out_dir` relative to its input directory
+fn output_path(file: &Path, roots: &[PathBuf], out_dir: Option<&Path>, extension: &str) -> PathBuf {
+    let Some(out_dir) = out_dir else {
+        return file.with_extension(extension);
+    };
+
+    let relative = roots
+        .iter()
+        .filter(|root| root.is_dir())
+        .find_map(|root| file.strip_prefix(root).ok())
+        .map(Path::to_path_buf)
+        .or_else(|| file.file_name().map(PathBuf::from))
+        .unwrap_or_else(|| file.to_path_buf());
+
+    out_dir.join(relative).with_extension(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path_next_to_input() {
+        let path = output_path(Path::new("songs/a.chords"), &[], None, "svg");
+        assert_eq!(path, Path::new("songs/a.svg"));
+    }
+
+    #[test]
+    fn test_output_path_mirrors_directory() {
+        let root = std::env::temp_dir();
+        let file = root.join("set/a.chords");
+        let path = output_path(&file, &[root], Some(Path::new("out")), "pdf");
+        assert_eq!(path, Path::new("out/set/a.pdf"));
+
+        let path = output_path(Path::new("b.chords"), &[], Some(Path::new("out")), "svg");
+        assert_eq!(path, Path::new("out/b.svg"));
+    }
+
+    #[test]
+    fn test_backend_lookup() {
+        let registry = Registry::default();
+        assert_eq!(backend(&registry, "html").unwrap().extension(), "html");
+        assert!(backend(&registry, "docx").is_err());
+        assert_eq!(backend(&registry, "pdf").is_ok(), cfg!(feature = "pdf"));
+    }
+}
@@ -0,0 +1,210 @@
+// Live preview server: watches a chart file and pushes re-rendered SVG over server-sent events
+
+use super::watch::Watcher;
+use super::{render_file, report_to_string, CliError, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long a write to a preview client may stall before the client is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shared state between the watcher and connection threads
+struct Preview {
+    /// Latest rendered fragment (SVG or error report)
+    fragment: Mutex<String>,
+    /// Queues of the open server-sent event streams, each written by its connection's thread
+    subscribers: Mutex<Vec<Sender<String>>>,
+}
+
+impl Preview {
+    fn update(&self, fragment: String) {
+        let event = sse_event("chart", &fragment);
+        *self.fragment.lock().unwrap() = fragment;
+
+        // Sending never blocks on a slow client; streams that have gone away are dropped
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+/// Serve a live preview of `file` on localhost until interrupted
+pub fn serve(file: &Path, port: u16) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map_err(|source| CliError::Serve { port, source })?;
+
+    let preview = Arc::new(Preview {
+        fragment: Mutex::new(render_fragment(file)),
+        subscribers: Mutex::new(Vec::new()),
+    });
+
+    let watched = file.to_path_buf();
+    let watcher = Arc::clone(&preview);
+    thread::spawn(move || watch(&watched, &watcher));
+
+    println!("Serving {} at http://localhost:{}/", file.display(), port);
+
+    for stream in listener.incoming().flatten() {
+        let preview = Arc::clone(&preview);
+        let title = file.display().to_string();
+        thread::spawn(move || {
+            let _ = handle_connection(stream, &preview, &title);
+        });
+    }
+
+    Ok(())
+}
+
+/// Render the file to an HTML fragment: the SVG itself, or the error report in a `<pre>`
+fn render_fragment(file: &Path) -> String {
+    match render_file(file) {
+        Ok(svg) => svg,
+        Err(error) => format!(
+            "<pre class=\"error\">{}</pre>",
+            escape_html(&report_to_string(&error))
+        ),
+    }
+}
+
+fn watch(file: &Path, preview: &Preview) {
+    let mut watcher = Watcher::new(vec![file.to_path_buf()]);
+    loop {
+        watcher.wait_for_changes();
+        preview.update(render_fragment(file));
+    }
+}
+
+fn handle_connection(stream: TcpStream, preview: &Preview, title: &str) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Skip the remaining request headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let mut stream = stream;
+    match path {
+        "/" => {
+            let fragment = preview.fragment.lock().unwrap().clone();
+            let body = page(title, &fragment);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        "/events" => {
+            stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+            )?;
+            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            let (sender, events) = mpsc::channel();
+            preview.subscribers.lock().unwrap().push(sender);
+            for event in events {
+                stream.write_all(event.as_bytes())?;
+            }
+            Ok(())
+        }
+        _ => stream.write_all(
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ),
+    }
+}
+
+/// Format a server-sent event; multi-line data is split across `data:` fields
+fn sse_event(event: &str, data: &str) -> String {
+    let mut output = format!("event: {}\n", event);
+    for line in data.lines() {
+        output.push_str("data: ");
+        output.push_str(line);
+        output.push('\n');
+    }
+    output.push('\n');
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page(title: &str, fragment: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+  body {{ margin: 0; background: #e8e8e8; display: flex; justify-content: center; }}
+  #chart {{ margin: 24px; }}
+  #chart svg {{ background: #fff; box-shadow: 0 1px 4px rgba(0, 0, 0, 0.3); }}
+  pre.error {{ background: #fff; color: #b00020; padding: 16px; font-size: 13px; }}
+</style>
+</head>
+<body>
+<div id="chart">{fragment}</div>
+<script>
+  const source = new EventSource("/events");
+  source.addEventListener("chart", (event) => {{
+    document.getElementById("chart").innerHTML = event.data;
+  }});
+</script>
+</body>
+</html>
+"#,
+        title = escape_html(title),
+        fragment = fragment
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_sse_event_splits_lines() {
+        let event = sse_event("chart", "<svg>\n<text/>\n</svg>");
+        assert_eq!(event, "event: chart\ndata: <svg>\ndata: <text/>\ndata: </svg>\n\n");
+    }
+
+    #[test]
+    fn test_error_fragment_is_escaped() {
+        let file = std::env::temp_dir().join(format!("chord-script-serve-test-{}.chords", std::process::id()));
+        fs::write(&file, "=== *Unclosed <italic").unwrap();
+        let fragment = render_fragment(&file);
+        fs::remove_file(&file).unwrap();
+
+        assert!(fragment.starts_with("<pre class=\"error\">"));
+        assert!(fragment.contains("&lt;italic"));
+    }
+
+    #[test]
+    fn test_update_drops_closed_subscribers() {
+        let preview = Preview {
+            fragment: Mutex::new(String::new()),
+            subscribers: Mutex::new(Vec::new()),
+        };
+        let (open, events) = mpsc::channel();
+        let (closed, _) = mpsc::channel();
+        preview.subscribers.lock().unwrap().extend([open, closed]);
+
+        preview.update("<svg/>".to_string());
+        assert_eq!(events.try_recv().unwrap(), "event: chart\ndata: <svg/>\n\n");
+        assert_eq!(preview.subscribers.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,93 @@
+// Compile a setlist manifest into a single paginated document
+
+use super::render::{backend, write_assets};
+use super::{read_chart, write_file, CliError, Result};
+use chord_script::model::Page;
+use chord_script::parser::parse_setlist;
+use chord_script::render::{Output, Registry, Renderer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Read the manifest and every chart it lists, then write the compiled document
+pub fn compile(manifest: &Path, format: &str, output: Option<&Path>) -> Result<()> {
+    let registry = Registry::default();
+    let renderer = backend(&registry, format)?;
+
+    let pages = read_pages(manifest, renderer)?;
+    match renderer.render_pages(&pages)? {
+        Output::Document(bytes) => {
+            let path = output
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| manifest.with_extension(renderer.extension()));
+            write_assets(&path, renderer)?;
+            write_file(&path, &bytes)?;
+            println!("✓ {} page(s) → {}", pages.len(), path.display());
+        }
+        Output::Pages(documents) => {
+            let dir = output
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| manifest.parent().unwrap_or(Path::new("")).to_path_buf());
+            for (index, bytes) in documents.iter().enumerate() {
+                let path = page_path(&dir, manifest, index + 1, renderer.extension());
+                if index == 0 {
+                    write_assets(&path, renderer)?;
+                }
+                write_file(&path, bytes)?;
+                println!("✓ page {} → {}", index + 1, path.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse the manifest and lay out its charts, numbering the sheets `renderer` fills; chart
+/// paths are relative to the manifest
+fn read_pages(manifest: &Path, renderer: &dyn Renderer) -> Result<Vec<Page>> {
+    let input = fs::read_to_string(manifest).map_err(|source| CliError::Read {
+        path: manifest.to_path_buf(),
+        source,
+    })?;
+    let setlist = parse_setlist(&input).map_err(|source| CliError::Parse {
+        path: manifest.to_path_buf(),
+        source,
+    })?;
+
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    let charts = setlist
+        .entries
+        .iter()
+        .map(|entry| read_chart(&base.join(&entry.path)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(setlist.paginate(charts, |page| renderer.sheets(page)))
+}
+
+/// Numbered page path, e.g. `out/friday-01.svg`
+fn page_path(dir: &Path, manifest: &Path, number: usize, extension: &str) -> PathBuf {
+    let stem = manifest
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "setlist".to_string());
+    dir.join(format!("{}-{:02}.{}", stem, number, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chord_script::render::TextGenerator;
+
+    #[test]
+    fn test_read_pages_resolves_relative_paths() {
+        let dir = std::env::temp_dir().join("chord-script-setlist-test");
+        fs::create_dir_all(dir.join("songs")).unwrap();
+        fs::write(dir.join("songs/one.chords"), "=== <>One\nC F\n").unwrap();
+        fs::write(dir.join("gig.setlist"), "title: Gig\nsongs/one.chords | key: D\n").unwrap();
+
+        let pages = read_pages(&dir.join("gig.setlist"), &TextGenerator::with_defaults());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let pages = pages.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].chart.lines[1].chords[0].to_string(), "D");
+        assert_eq!(page_path(Path::new("out"), Path::new("gig.setlist"), 2, "svg"), Path::new("out/gig-02.svg"));
+    }
+}
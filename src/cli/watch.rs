@@ -0,0 +1,126 @@
+// Polling file watcher for chart files and directories
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// File extension of chart sources picked up from directories
+pub const CHART_EXTENSION: &str = "chords";
+
+/// How often modification times are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Quiet period required after the last change before reporting a batch
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Chart files under `roots`: files are taken as given, directories are searched recursively
+pub fn chart_files(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in roots {
+        if root.is_dir() {
+            collect_charts(root, &mut files);
+        } else {
+            files.push(root.clone());
+        }
+    }
+    files.sort();
+    files
+}
+
+fn collect_charts(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_charts(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == CHART_EXTENSION) {
+            files.push(path);
+        }
+    }
+}
+
+/// Watches chart files by polling their modification times
+pub struct Watcher {
+    roots: Vec<PathBuf>,
+    seen: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Watcher {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        let mut watcher = Self {
+            roots,
+            seen: HashMap::new(),
+        };
+        watcher.seen = watcher.scan();
+        watcher
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, Option<SystemTime>> {
+        chart_files(&self.roots)
+            .into_iter()
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+
+    /// Files added or modified since the previous poll, sorted
+    fn poll(&mut self) -> Vec<PathBuf> {
+        let current = self.scan();
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, modified)| self.seen.get(*path) != Some(*modified))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.sort();
+        self.seen = current;
+        changed
+    }
+
+    /// Block until files change, then wait for a quiet period so a burst of saves is reported once
+    pub fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let mut changed = self.poll();
+            if changed.is_empty() {
+                continue;
+            }
+            loop {
+                thread::sleep(DEBOUNCE);
+                let more = self.poll();
+                if more.is_empty() {
+                    break;
+                }
+                changed.extend(more);
+            }
+            changed.sort();
+            changed.dedup();
+            return changed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chart_files_filters_directories_by_extension() {
+        let dir = std::env::temp_dir().join("chord-script-watch-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("set")).unwrap();
+        fs::write(dir.join("a.chords"), "= A").unwrap();
+        fs::write(dir.join("set/b.chords"), "= B").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let files = chart_files(std::slice::from_ref(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, [dir.join("a.chords"), dir.join("set/b.chords")]);
+    }
+}
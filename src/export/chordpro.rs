@@ -0,0 +1,155 @@
+// ChordPro export
+//
+// Headers map to metadata directives and comments, chord lines to bracketed chords with
+// `|` bar lines and `|:`/`:|` repeat signs. Alignment and inline styling have no ChordPro
+// equivalent and are dropped.
+
+use crate::model::{Bar, Beat, Chart, ChordItem, Line, LineLevel, Measure, TextSpan};
+
+/// Write a chart as a ChordPro document
+pub fn export(chart: &Chart) -> String {
+    let mut output = String::new();
+    let key = chart.key();
+    // The key follows the title so readers pick it up as song metadata; without a title it
+    // opens the document
+    let mut key_written = false;
+    if let (Some(key), false) = (key, chart.lines.iter().any(|line| line.level == LineLevel::Header1)) {
+        output.push_str(&format!("{{key: {}}}\n", key));
+        key_written = true;
+    }
+
+    for (line, measures) in chart.lines.iter().zip(chart.line_measures()) {
+        // The `Key:` label became the `{key}` directive
+        let text = line_text(line, key.is_some());
+        match line.level {
+            LineLevel::Header1 => {
+                output.push_str(&format!("{{title: {}}}\n", text));
+                if let (false, Some(key)) = (key_written, key) {
+                    output.push_str(&format!("{{key: {}}}\n", key));
+                    key_written = true;
+                }
+            }
+            _ if line.level != LineLevel::Chords && text.is_empty() => {}
+            LineLevel::Header2 => output.push_str(&format!("{{subtitle: {}}}\n", text)),
+            LineLevel::Header3 => output.push_str(&format!("{{comment: {}}}\n", text)),
+            LineLevel::Text => output.push_str(&format!("{{comment_italic: {}}}\n", text)),
+            LineLevel::Chords => {
+                output.push_str(&chord_line(&line.chords, &measures));
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+/// Text of all non-empty columns, left to right, without styling; `skip_key` leaves out
+/// columns holding the `Key:` label
+fn line_text(line: &Line, skip_key: bool) -> String {
+    [&line.left, &line.center, &line.right]
+        .into_iter()
+        .map(|spans| TextSpan::join(spans))
+        .filter(|text| !text.is_empty())
+        .filter(|text| {
+            let label = text.split_whitespace().next().unwrap_or_default();
+            !(skip_key && label.eq_ignore_ascii_case("key:"))
+        })
+        .collect::<Vec<_>>()
+        .join(" — ")
+}
+
+/// Bars between barlines, with repeat signs where the line's measures start and close repeats
+fn chord_line(items: &[ChordItem], measures: &[Measure]) -> String {
+    let mut bars = Vec::new();
+    let mut prefix = String::new();
+    push_bars(items, None, &mut bars, &mut prefix);
+
+    let mut text = String::new();
+    for index in 0..=bars.len() {
+        let close = index
+            .checked_sub(1)
+            .filter(|&previous| measures.get(previous).is_some_and(|measure| measure.repeat_end.is_some()))
+            .map(|previous| match bars[previous].1 {
+                Some(times) => format!(":| x{}", times),
+                None => ":|".to_string(),
+            });
+        let open = measures.get(index).is_some_and(|measure| measure.repeat_start);
+        let barline = match (close, open) {
+            (None, false) => "|".to_string(),
+            (None, true) => "|:".to_string(),
+            (Some(close), false) => close,
+            (Some(close), true) if close == ":|" => ":|:".to_string(),
+            (Some(close), true) => format!("{} |:", close),
+        };
+        text.push_str(&barline);
+        if let Some((bar, _)) = bars.get(index) {
+            text.push_str(&format!(" {} ", bar));
+        }
+    }
+    text
+}
+
+/// Text of each bar in `items`, with the written times of the repeat it is in; endings and
+/// annotations lead the bar they come before
+fn push_bars(items: &[ChordItem], times: Option<u32>, bars: &mut Vec<(String, Option<u32>)>, prefix: &mut String) {
+    for item in items {
+        match item {
+            ChordItem::Bar(bar) => bars.push((format!("{}{}", std::mem::take(prefix), bar_text(bar)), times)),
+            ChordItem::RepeatBar => bars.push((format!("{}%", std::mem::take(prefix)), times)),
+            ChordItem::Group(group) => push_bars(&group.items, group.times, bars, prefix),
+            ChordItem::Ending(number) => prefix.push_str(&format!("{}. ", number)),
+            ChordItem::Annotation(text) => prefix.push_str(&format!("{} ", text)),
+        }
+    }
+}
+
+/// Chords in brackets, held beats as `/`
+fn bar_text(bar: &Bar) -> String {
+    bar.beats
+        .iter()
+        .map(|beat| match beat {
+            Beat::Chord(hit) => format!("[{}]", hit.chord),
+            Beat::NoChord => "[N.C.]".to_string(),
+            Beat::Empty => "/".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    #[test]
+    fn test_export_chordpro() {
+        let chart = parse_chart(
+            "=== <>Blue Bossa\n== <>Kenny Dorham\n= <Head >2x\n- <>*Latin*\nCm7 % Fm7_Bb7 (Ebmaj7 N.C.) 2x\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            export(&chart),
+            "{title: Blue Bossa}\n\
+             {subtitle: Kenny Dorham}\n\
+             {comment: Head — 2x}\n\
+             {comment_italic: Latin}\n\
+             | [Cm7] | % | [Fm7] [Bb7] |: [Ebmaj7] | [N.C.] :| x2\n"
+        );
+    }
+
+    #[test]
+    fn test_export_endings_and_key() {
+        let chart = parse_chart("=== <>Song\n- >Key: Gm\n(Gm 1. D7 2. Gm)\n").unwrap();
+        assert_eq!(
+            export(&chart),
+            "{title: Song}\n\
+             {key: Gm}\n\
+             |: [Gm] | 1. [D7] :| 2. [Gm] |\n"
+        );
+
+        // Without a title the key opens the document
+        let chart = parse_chart("= <A >Key: Gm\nGm\n").unwrap();
+        assert_eq!(export(&chart), "{key: Gm}\n{comment: A}\n| [Gm] |\n");
+    }
+}
@@ -0,0 +1,222 @@
+// iReal Pro URL export
+//
+// Encodes the chart's measures as an iReal chord progression: sections become rehearsal
+// marks, repeat groups `{ }` with `N1`/`N2` endings, and each bar's beat slots its chords.
+
+use crate::import::ireal::{percent_encode, scramble, MUSIC_PREFIX};
+use crate::model::{Beat, Chart, Chord, Measure, TimeSignature};
+
+/// Style written to the URL; iReal uses it to pick a playback groove
+const DEFAULT_STYLE: &str = "Medium Swing";
+
+/// URL flavour to produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlFormat {
+    /// `irealb://`, the obfuscated format current iReal Pro versions share
+    IRealB,
+    /// `irealbook://`, the older plain-text format
+    IRealBook,
+}
+
+/// Encode a chart's chord content as an iReal Pro song URL
+pub fn export(chart: &Chart, format: UrlFormat) -> String {
+    let field = |text: Option<String>| text.unwrap_or_default().replace('=', "-");
+    let title = field(chart.title());
+    let composer = field(chart.subtitle());
+    let measures = chart.measures();
+    let key = chart
+        .key_or_first_chord()
+        .map(|key| format!("{}{}", key.tonic, if key.minor { "-" } else { "" }))
+        .unwrap_or_else(|| "C".to_string());
+    let music = music(chart, &measures);
+
+    match format {
+        UrlFormat::IRealB => format!(
+            "irealb://{}",
+            percent_encode(&format!(
+                "{}={}=={}={}=={}{}==0=0",
+                title,
+                composer,
+                DEFAULT_STYLE,
+                key,
+                MUSIC_PREFIX,
+                scramble(&music)
+            ))
+        ),
+        UrlFormat::IRealBook => format!(
+            "irealbook://{}",
+            percent_encode(&format!("{}={}={}={}=n={}", title, composer, DEFAULT_STYLE, key, music))
+        ),
+    }
+}
+
+/// iReal's code for a time signature; it has none for meters such as 7/16 or 10/4
+pub fn time_code(time: TimeSignature) -> Option<&'static str> {
+    let code = match (time.beats, time.beat_type) {
+        (2, 4) => "T24",
+        (3, 4) => "T34",
+        (4, 4) => "T44",
+        (5, 4) => "T54",
+        (6, 4) => "T64",
+        (7, 4) => "T74",
+        (2, 2) => "T22",
+        (3, 2) => "T32",
+        (5, 8) => "T58",
+        (6, 8) => "T68",
+        (7, 8) => "T78",
+        (9, 8) => "T98",
+        (12, 8) => "T12",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// The plain (unobfuscated) chord progression
+fn music(chart: &Chart, measures: &[Measure]) -> String {
+    // Meters iReal has no code for are left out, and play as 4/4
+    let mut music = chart.time_signature().and_then(time_code).unwrap_or("T44").to_string();
+
+    for (index, measure) in measures.iter().enumerate() {
+        let next = measures.get(index + 1);
+
+        if let Some(section) = &measure.section {
+            match rehearsal_mark(section) {
+                Some(mark) => {
+                    music.push('*');
+                    music.push(mark);
+                }
+                None => music.push_str(&format!("<{}>", section)),
+            }
+        }
+        // Rehearsal marks come first: iReal closes any open repeat at a new section
+        if measure.repeat_start {
+            music.push('{');
+        } else if index == 0 || measure.section.is_some() {
+            music.push('[');
+        }
+        if let Some(volta) = measure.ending.filter(|volta| volta.start) {
+            music.push_str(&format!("N{}", volta.number));
+        }
+        for annotation in &measure.annotations {
+            music.push_str(&format!("<{}>", annotation));
+        }
+
+        let beats: Vec<String> = measure
+            .bar
+            .beats
+            .iter()
+            .enumerate()
+            .map(|(slot, beat)| {
+                let fermata = if measure.bar.fermata && slot + 1 == measure.bar.beats.len() { "f" } else { "" };
+                let symbol = match beat {
+                    Beat::Chord(hit) => chord_symbol(&hit.chord),
+                    Beat::NoChord => "n".to_string(),
+                    Beat::Empty => "p".to_string(),
+                };
+                format!("{}{}", fermata, symbol)
+            })
+            .collect();
+        music.push_str(&beats.join(" "));
+        music.push(' ');
+
+        let closing = if measure.repeat_end.is_some() {
+            "}"
+        } else if next.is_none() {
+            "Z"
+        } else if next.is_some_and(|next| next.section.is_some() && !next.repeat_start) {
+            "]"
+        } else if next.is_some_and(|next| next.repeat_start) {
+            ""
+        } else {
+            "|"
+        };
+        music.push_str(closing);
+    }
+
+    music
+}
+
+/// iReal's rehearsal marks: letters A to D, plus intro and verse
+fn rehearsal_mark(section: &str) -> Option<char> {
+    match section.to_ascii_lowercase().as_str() {
+        "a" => Some('A'),
+        "b" => Some('B'),
+        "c" => Some('C'),
+        "d" => Some('D'),
+        "intro" => Some('i'),
+        "verse" => Some('V'),
+        _ => None,
+    }
+}
+
+/// Chord in iReal spelling (`m7` → `-7`, `maj7` → `^7`, `m7b5` → `h7`)
+fn chord_symbol(chord: &Chord) -> String {
+    let quality = chord.quality.as_str();
+    let quality = if let Some(rest) = quality.strip_prefix("m7b5").or(quality.strip_prefix("ø7")).or(quality.strip_prefix('ø')) {
+        format!("h7{}", rest)
+    } else if let Some(rest) = quality.strip_prefix("mmaj") {
+        format!("-^{}", rest)
+    } else if quality == "maj" {
+        String::new()
+    } else if let Some(rest) = quality.strip_prefix("maj") {
+        format!("^{}", rest)
+    } else if let Some(rest) = quality.strip_prefix("dim") {
+        format!("o{}", rest)
+    } else if let Some(rest) = quality.strip_prefix("aug") {
+        format!("+{}", rest)
+    } else if let Some(rest) = quality.strip_prefix("min").or(quality.strip_prefix('m')) {
+        format!("-{}", rest)
+    } else {
+        quality.to_string()
+    };
+
+    match chord.bass {
+        Some(bass) => format!("{}{}/{}", chord.root, quality, bass),
+        None => format!("{}{}", chord.root, quality),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::ireal::import;
+    use crate::parser::parse_chart;
+
+    const CHART: &str = "=== <>Blue Bossa\n== <>Dorham Kenny\n- >3/4\n= A\n(Cm7, % Fm7_Bb7 1. Ebmaj7 2. Dm7b5_G7b9)\n= B\nEbm7 Ab7 Dbmaj7 fermata\n";
+
+    #[test]
+    fn test_music_string() {
+        let chart = parse_chart(CHART).unwrap();
+        assert_eq!(
+            music(&chart, &chart.measures()),
+            "T34*A{C-7 p |C-7 p |F-7 Bb7 |N1Eb^7 }N2Dh7 G7b9 ]*B[Eb-7 |Ab7 |fDb^7 Z"
+        );
+
+        let chart = parse_chart("- >7/16\nC\n").unwrap();
+        assert!(music(&chart, &chart.measures()).starts_with("T44"));
+    }
+
+    #[test]
+    fn test_key_from_key_line() {
+        let chart = parse_chart("=== <>Song\n= >Key: Gm\nAm7b5 D7 Gm\n").unwrap();
+        let url = export(&chart, UrlFormat::IRealBook);
+        assert!(url.contains("=G-=n="));
+        assert!(!url.contains("Key"));
+
+        let chart = parse_chart("=== <>Song\nAm7b5 D7 Gm\n").unwrap();
+        assert!(export(&chart, UrlFormat::IRealBook).contains("=A-=n="));
+    }
+
+    #[test]
+    fn test_round_trip_through_both_formats() {
+        let chart = parse_chart(CHART).unwrap();
+        for format in [UrlFormat::IRealB, UrlFormat::IRealBook] {
+            let url = export(&chart, format);
+            let imported = import(&url).unwrap();
+            assert!(imported.warnings.is_empty());
+            assert_eq!(imported.chart.title().as_deref(), Some("Blue Bossa"));
+            assert_eq!(imported.chart.time_signature().map(|time| time.to_string()).as_deref(), Some("3/4"));
+            assert_eq!(imported.chart.playback(), chart.playback());
+        }
+    }
+}
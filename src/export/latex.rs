@@ -0,0 +1,255 @@
+// LaTeX export for printed songbooks
+//
+// Produces a standalone document needing only the standard `geometry` and `array` packages.
+// Every line goes through a small set of `\chart...` macros defined in the preamble, so a
+// songbook can restyle the charts by redefining them. Chord lines become tables of bars,
+// four to a row, with repeat signs, endings and annotations inside the cells.
+
+use crate::model::{Accidental, Beat, Chart, Chord, ChordItem, LineLevel, Measure, Note, TextSpan, TextStyle};
+
+/// Bars per table row, as in a printed real book; matches the `chartgrid` columns in the preamble
+const BARS_PER_ROW: usize = 4;
+
+const PREAMBLE: &str = r"\documentclass[11pt]{article}
+\usepackage[utf8]{inputenc}
+\usepackage[T1]{fontenc}
+\usepackage[margin=2cm]{geometry}
+\usepackage{array}
+\setlength{\parindent}{0pt}
+\pagestyle{empty}
+
+% Three-column line: left, centered and right-aligned text
+\newcommand{\chartline}[3]{\makebox[0pt][l]{#1}\hfill\makebox[0pt][c]{#2}\hfill\makebox[0pt][r]{#3}\par}
+\newcommand{\charttitle}[3]{{\LARGE\bfseries\chartline{#1}{#2}{#3}}\medskip}
+\newcommand{\chartsubtitle}[3]{{\large\chartline{#1}{#2}{#3}}\medskip}
+\newcommand{\chartsection}[3]{\medskip{\bfseries\chartline{#1}{#2}{#3}}\smallskip}
+\newcommand{\charttext}[3]{{\itshape\chartline{#1}{#2}{#3}}}
+
+% Chord grid cells
+\newenvironment{chartgrid}{\par\smallskip\renewcommand{\arraystretch}{1.6}\begin{tabular}{|*{4}{p{0.2\linewidth}|}}\hline}{\end{tabular}\par\smallskip}
+\newcommand{\chartrepeatstart}{\textbf{\textbar:}\ }
+\newcommand{\chartrepeatend}[1]{\hfill\textbf{:\textbar}#1}
+\newcommand{\chartending}[1]{\textsuperscript{#1.}\,}
+\newcommand{\chartannotation}[1]{\textit{\small #1}\ }
+\newcommand{\chartfermata}{\ \ensuremath{\frown}}
+";
+
+/// Write a chart as a compilable LaTeX document
+pub fn export(chart: &Chart) -> String {
+    let mut output = String::from(PREAMBLE);
+    output.push_str("\n\\begin{document}\n\n");
+
+    for (line, measures) in chart.lines.iter().zip(chart.line_measures()) {
+        let command = match line.level {
+            LineLevel::Header1 => "charttitle",
+            LineLevel::Header2 => "chartsubtitle",
+            LineLevel::Header3 => "chartsection",
+            LineLevel::Text => "charttext",
+            LineLevel::Chords => {
+                output.push_str(&grid(&line.chords, &measures));
+                continue;
+            }
+        };
+        output.push_str(&format!(
+            "\\{}{{{}}}{{{}}}{{{}}}\n",
+            command,
+            styled_text(&line.left),
+            styled_text(&line.center),
+            styled_text(&line.right)
+        ));
+    }
+
+    output.push_str("\n\\end{document}\n");
+    output
+}
+
+/// Spans as `\textbf`/`\textit`, special characters escaped
+fn styled_text(spans: &[TextSpan]) -> String {
+    spans
+        .iter()
+        .filter(|span| !span.text.trim().is_empty())
+        .map(|span| {
+            let text = escape(span.text.trim());
+            match span.style {
+                TextStyle::Normal => text,
+                TextStyle::Bold => format!("\\textbf{{{}}}", text),
+                TextStyle::Italic => format!("\\textit{{{}}}", text),
+                TextStyle::BoldItalic => format!("\\textbf{{\\textit{{{}}}}}", text),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A bar of the grid, with the repeat signs around it
+#[derive(Default)]
+struct GridCell {
+    content: String,
+    repeat_start: bool,
+    repeat_end: Option<Option<u32>>,
+    /// Written times of the repeat the bar is in
+    times: Option<u32>,
+}
+
+/// Chord line as a `chartgrid` table; the last row is padded with empty cells. Repeat signs go
+/// where the line's measures start and close repeats.
+fn grid(items: &[ChordItem], measures: &[Measure]) -> String {
+    let mut cells = Vec::new();
+    let mut prefix = String::new();
+    push_cells(items, None, &mut cells, &mut prefix);
+    for (cell, measure) in cells.iter_mut().zip(measures) {
+        cell.repeat_start = measure.repeat_start;
+        cell.repeat_end = measure.repeat_end.map(|_| cell.times);
+    }
+    if cells.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("\\begin{chartgrid}\n");
+    for row in cells.chunks(BARS_PER_ROW) {
+        let mut texts: Vec<String> = row
+            .iter()
+            .map(|cell| {
+                let start = if cell.repeat_start { "\\chartrepeatstart " } else { "" };
+                let end = match cell.repeat_end {
+                    Some(Some(times)) => format!("\\chartrepeatend{{ x{}}}", times),
+                    Some(None) => "\\chartrepeatend{}".to_string(),
+                    None => String::new(),
+                };
+                format!("{}{}{}", start, cell.content, end)
+            })
+            .collect();
+        texts.resize(BARS_PER_ROW, String::new());
+        output.push_str(&format!("{} \\\\ \\hline\n", texts.join(" & ")));
+    }
+    output.push_str("\\end{chartgrid}\n");
+    output
+}
+
+fn push_cells(items: &[ChordItem], times: Option<u32>, cells: &mut Vec<GridCell>, prefix: &mut String) {
+    for item in items {
+        match item {
+            ChordItem::Bar(bar) => {
+                let beats: Vec<String> = bar
+                    .beats
+                    .iter()
+                    .map(|beat| match beat {
+                        Beat::Chord(hit) => {
+                            let chord = chord_text(&hit.chord);
+                            let chord = if hit.accent { format!("\\textbf{{{}}}", chord) } else { chord };
+                            let chord = if hit.push { format!("\\ensuremath{{<}}{}", chord) } else { chord };
+                            if hit.ghost {
+                                format!("({})", chord)
+                            } else {
+                                chord
+                            }
+                        }
+                        Beat::NoChord => "N.C.".to_string(),
+                        Beat::Empty => "/".to_string(),
+                    })
+                    .collect();
+                let fermata = if bar.fermata { "\\chartfermata" } else { "" };
+                cells.push(GridCell {
+                    content: format!("{}{}{}", std::mem::take(prefix), beats.join(" "), fermata),
+                    times,
+                    ..GridCell::default()
+                });
+            }
+            ChordItem::RepeatBar => cells.push(GridCell {
+                content: format!("{}\\%", std::mem::take(prefix)),
+                times,
+                ..GridCell::default()
+            }),
+            ChordItem::Group(group) => push_cells(&group.items, group.times, cells, prefix),
+            ChordItem::Ending(number) => prefix.push_str(&format!("\\chartending{{{}}}", number)),
+            ChordItem::Annotation(text) => prefix.push_str(&format!("\\chartannotation{{{}}}", escape(text))),
+        }
+    }
+}
+
+/// Chord symbol with typeset accidentals on the root and bass
+fn chord_text(chord: &Chord) -> String {
+    let mut text = format!("{}{}", note_text(chord.root), escape(&chord.quality));
+    if let Some(bass) = chord.bass {
+        text.push_str(&format!("/{}", note_text(bass)));
+    }
+    text
+}
+
+fn note_text(note: Note) -> String {
+    let accidental = match note.accidental {
+        Accidental::DoubleFlat => "\\ensuremath{\\flat\\flat}",
+        Accidental::Flat => "\\ensuremath{\\flat}",
+        Accidental::Natural => "",
+        Accidental::Sharp => "\\ensuremath{\\sharp}",
+        Accidental::DoubleSharp => "\\ensuremath{\\sharp\\sharp}",
+    };
+    format!("{}{}", note.name.as_char(), accidental)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            // Chord symbol glyphs that `inputenc` has no definition for
+            'Δ' | '△' => escaped.push_str("\\ensuremath{\\Delta}"),
+            '°' => escaped.push_str("\\ensuremath{^\\circ}"),
+            '♭' => escaped.push_str("\\ensuremath{\\flat}"),
+            '♯' => escaped.push_str("\\ensuremath{\\sharp}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    #[test]
+    fn test_export_lines() {
+        let chart = parse_chart("=== <>Blue Bossa\n= <A >**Bossa** & *Latin*\n- <50% faster\n").unwrap();
+        let latex = export(&chart);
+
+        assert!(latex.starts_with("\\documentclass"));
+        assert!(latex.contains(
+            "\\begin{document}\n\n\
+             \\charttitle{}{Blue Bossa}{}\n\
+             \\chartsection{A}{}{\\textbf{Bossa} \\& \\textit{Latin}}\n\
+             \\charttext{50\\% faster}{}{}\n\
+             \n\\end{document}\n"
+        ));
+    }
+
+    #[test]
+    fn test_export_grid() {
+        let chart = parse_chart("F#m7b5 % (<>Bb7_Eb/G 1. C 2. D? fermata) 2x \"To coda\" G\n").unwrap();
+
+        assert_eq!(
+            grid(&chart.lines[0].chords, &chart.measures()),
+            "\\begin{chartgrid}\n\
+             F\\ensuremath{\\sharp}m7b5 & \\% & \\chartrepeatstart \\textbf{B\\ensuremath{\\flat}7} E\\ensuremath{\\flat}/G & \\chartending{1}C\\chartrepeatend{ x2} \\\\ \\hline\n\
+             \\chartending{2}(D)\\chartfermata & \\chartannotation{To coda}G &  &  \\\\ \\hline\n\
+             \\end{chartgrid}\n"
+        );
+    }
+
+    #[test]
+    fn test_chord_glyphs_are_typeset() {
+        let chart = parse_chart("CΔ7 F♯m7♭5 B°7\n").unwrap();
+        let latex = grid(&chart.lines[0].chords, &chart.measures());
+
+        assert!(latex.contains("C\\ensuremath{\\Delta}7"));
+        assert!(latex.contains("F\\ensuremath{\\sharp}m7\\ensuremath{\\flat}5"));
+        assert!(latex.contains("B\\ensuremath{^\\circ}7"));
+        assert!(!latex.contains(['Δ', '♯', '♭', '°']));
+    }
+}
@@ -0,0 +1,148 @@
+// Markdown (CommonMark) export
+//
+// Headers become `#` headings and text lines paragraphs; a text line using more than one
+// column becomes a one-row table aligned left, center and right. Runs of chord lines become
+// a fenced code block holding the plain-text chord grid, so barlines line up in any viewer.
+
+use crate::model::{Chart, Line, LineLevel, TextSpan, TextStyle};
+use crate::render::TextGenerator;
+
+/// Write a chart as a Markdown document
+pub fn export(chart: &Chart) -> String {
+    let mut blocks = Vec::new();
+    let mut grid: Vec<Line> = Vec::new();
+
+    for line in &chart.lines {
+        if line.level == LineLevel::Chords {
+            grid.push(line.clone());
+            continue;
+        }
+        if !grid.is_empty() {
+            blocks.push(code_block(std::mem::take(&mut grid)));
+        }
+        if let Some(block) = line_block(line) {
+            blocks.push(block);
+        }
+    }
+    if !grid.is_empty() {
+        blocks.push(code_block(grid));
+    }
+
+    let mut output = blocks.join("\n\n");
+    output.push('\n');
+    output
+}
+
+/// Heading, paragraph or table for a text line; `None` when every column is empty
+fn line_block(line: &Line) -> Option<String> {
+    let columns: Vec<String> = [&line.left, &line.center, &line.right]
+        .into_iter()
+        .map(|spans| styled_text(spans))
+        .collect();
+    let filled: Vec<&str> = columns.iter().map(String::as_str).filter(|text| !text.is_empty()).collect();
+    if filled.is_empty() {
+        return None;
+    }
+
+    let prefix = match line.level {
+        LineLevel::Header1 => "# ",
+        LineLevel::Header2 => "## ",
+        LineLevel::Header3 => "### ",
+        LineLevel::Text | LineLevel::Chords => {
+            if filled.len() == 1 {
+                return Some(escape_block_start(filled[0]));
+            }
+            // A header row is required, so the line itself is the header of an otherwise empty table
+            let cells: Vec<String> = columns.iter().map(|text| text.replace('|', "\\|")).collect();
+            return Some(format!("| {} |\n|:---|:---:|---:|", cells.join(" | ")));
+        }
+    };
+    // Headings hold a single line of text, so columns are joined
+    Some(format!("{}{}", prefix, filled.join(" — ")))
+}
+
+/// Spans with `*`, `**` and `***` emphasis, Markdown characters escaped
+fn styled_text(spans: &[TextSpan]) -> String {
+    spans
+        .iter()
+        .filter(|span| !span.text.trim().is_empty())
+        .map(|span| {
+            let text = escape(span.text.trim());
+            match span.style {
+                TextStyle::Normal => text,
+                TextStyle::Bold => format!("**{}**", text),
+                TextStyle::Italic => format!("*{}*", text),
+                TextStyle::BoldItalic => format!("***{}***", text),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Consecutive chord lines as one fenced block, laid out by the plain-text renderer
+fn code_block(lines: Vec<Line>) -> String {
+    let grid = TextGenerator::with_defaults().render(&Chart::new(lines));
+    format!("```\n{}```", grid)
+}
+
+/// Escape a paragraph's opening `-`, `+` or `1.` so it doesn't start a list
+fn escape_block_start(text: &str) -> String {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    match text[digits..].chars().next() {
+        Some('-' | '+') if digits == 0 => format!("\\{}", text),
+        Some('.' | ')') if digits > 0 => format!("{}\\{}", &text[..digits], &text[digits..]),
+        _ => text.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        if matches!(character, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    #[test]
+    fn test_export_markdown() {
+        let chart = parse_chart(
+            "=== <>Blue Bossa\n== <>Kenny Dorham\n= <A section\n- <Intro **loud** >***fast***\n- <Drums in_time\nCm7 %\n(Fm7 1. Bb7 2. G7) 2x\n- <>End\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            export(&chart),
+            "# Blue Bossa\n\
+             \n\
+             ## Kenny Dorham\n\
+             \n\
+             ### A section\n\
+             \n\
+             | Intro **loud** |  | ***fast*** |\n\
+             |:---|:---:|---:|\n\
+             \n\
+             Drums in\\_time\n\
+             \n\
+             ```\n\
+             |    Cm7    |    %      |\n\
+             |:   Fm7    |    1. Bb7 :|x2 2. G7  |\n\
+             ```\n\
+             \n\
+             End\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_list_markers() {
+        let chart = parse_chart("- <- fill\n- <+ horns\n- <2. chorus\n- <3) tag\n- <12 bars\n").unwrap();
+        assert_eq!(export(&chart), "\\- fill\n\n\\+ horns\n\n2\\. chorus\n\n3\\) tag\n\n12 bars\n");
+    }
+}
@@ -0,0 +1,256 @@
+// Standard MIDI file export for chord playback
+//
+// Writes a format 1 file with a tempo track and a block-chord track. Bars are played in
+// performance order (repeats unrolled), each beat slot taking an equal share of the bar.
+
+use crate::model::{Bar, Beat, Chart, ChordHit, TimeSignature};
+
+/// Ticks per quarter note
+const PPQ: u32 = 480;
+
+/// Tempo used when the chart does not state one
+const DEFAULT_TEMPO: u32 = 120;
+
+/// Bass notes sit in the octave below middle C, chord tones from middle C up
+const BASS_OCTAVE: i32 = 48;
+const CHORD_OCTAVE: i32 = 60;
+
+/// Velocities for ghost, normal and accented chords
+const GHOST_VELOCITY: u8 = 50;
+const NORMAL_VELOCITY: u8 = 80;
+const ACCENT_VELOCITY: u8 = 110;
+
+/// Render a chart's chords as a Standard MIDI File
+pub fn export(chart: &Chart) -> Vec<u8> {
+    let time = chart.time_signature().unwrap_or_default();
+    let tempo = chart.tempo().unwrap_or(DEFAULT_TEMPO).max(1);
+
+    let mut file = Vec::new();
+    file.extend_from_slice(b"MThd");
+    file.extend_from_slice(&6u32.to_be_bytes());
+    file.extend_from_slice(&1u16.to_be_bytes()); // format 1: simultaneous tracks
+    file.extend_from_slice(&2u16.to_be_bytes());
+    file.extend_from_slice(&(PPQ as u16).to_be_bytes());
+
+    write_track(&mut file, conductor_events(time, tempo));
+    write_track(&mut file, chord_events(&chart.playback(), time));
+    file
+}
+
+/// A MIDI event at an absolute tick
+struct Event {
+    tick: u32,
+    data: Vec<u8>,
+}
+
+/// Tempo and time signature meta events
+fn conductor_events(time: TimeSignature, tempo: u32) -> Vec<Event> {
+    // Microseconds per quarter note, in the 24 bits the event holds
+    let micros = (60_000_000 / tempo).clamp(1, 0xFF_FFFF).to_be_bytes();
+    vec![
+        Event {
+            tick: 0,
+            data: vec![0xFF, 0x51, 0x03, micros[1], micros[2], micros[3]],
+        },
+        Event {
+            tick: 0,
+            data: vec![0xFF, 0x58, 0x04, time.beats.min(u8::MAX.into()) as u8, time.beat_type.trailing_zeros() as u8, 24, 8],
+        },
+    ]
+}
+
+/// Block chords: each chord sounds from its beat slot until the next chord, `N.C.` or the end
+fn chord_events(bars: &[Bar], time: TimeSignature) -> Vec<Event> {
+    let measure = time.beats * PPQ * 4 / time.beat_type;
+    let mut events = vec![Event {
+        tick: 0,
+        data: b"\xFF\x03\x06Chords".to_vec(),
+    }];
+    let mut sounding: Vec<u8> = Vec::new();
+    let mut tick = 0;
+    // Tick of the latest strike or release, which pushes may not move ahead of
+    let mut latest = 0;
+
+    for bar in bars {
+        if bar.beats.is_empty() {
+            release(&mut events, &mut sounding, tick);
+            tick += measure;
+            continue;
+        }
+
+        let slot = measure / bar.beats.len() as u32;
+        for (index, beat) in bar.beats.iter().enumerate() {
+            let start = tick + slot * index as u32;
+            match beat {
+                Beat::Chord(hit) => {
+                    // Pushed chords land an eighth note early, but never before the chord they follow
+                    let start = if hit.push { start.saturating_sub(PPQ / 2).max(latest) } else { start };
+                    release(&mut events, &mut sounding, start);
+                    strike(&mut events, &mut sounding, hit, start);
+                    latest = start;
+                }
+                Beat::NoChord => {
+                    release(&mut events, &mut sounding, start);
+                    latest = start;
+                }
+                Beat::Empty => {}
+            }
+        }
+
+        tick += measure;
+        if bar.fermata {
+            // Hold the last beat slot for twice its length
+            tick += measure - slot * (bar.beats.len() as u32 - 1);
+        }
+    }
+    release(&mut events, &mut sounding, tick);
+
+    // Stable, so note-offs stay ahead of note-ons on the same tick
+    events.sort_by_key(|event| event.tick);
+    events
+}
+
+fn strike(events: &mut Vec<Event>, sounding: &mut Vec<u8>, hit: &ChordHit, tick: u32) {
+    let velocity = if hit.accent {
+        ACCENT_VELOCITY
+    } else if hit.ghost {
+        GHOST_VELOCITY
+    } else {
+        NORMAL_VELOCITY
+    };
+
+    let chord = &hit.chord;
+    let root = chord.root.pitch_class();
+    let bass = chord.bass.map_or(root, |bass| bass.pitch_class());
+    let mut keys = vec![(BASS_OCTAVE + bass) as u8];
+    keys.extend(
+        chord
+            .intervals()
+            .into_iter()
+            .map(|(_, semitones)| (CHORD_OCTAVE + root + semitones).min(127) as u8),
+    );
+    keys.dedup();
+
+    for &key in &keys {
+        events.push(Event {
+            tick,
+            data: vec![0x90, key, velocity],
+        });
+    }
+    *sounding = keys;
+}
+
+fn release(events: &mut Vec<Event>, sounding: &mut Vec<u8>, tick: u32) {
+    for key in sounding.drain(..) {
+        events.push(Event {
+            tick,
+            data: vec![0x80, key, 0],
+        });
+    }
+}
+
+/// Append an `MTrk` chunk holding `events` (sorted by tick) plus the end-of-track marker
+fn write_track(file: &mut Vec<u8>, events: Vec<Event>) {
+    let mut data = Vec::new();
+    let mut previous = 0;
+    for event in &events {
+        write_variable_length(&mut data, event.tick - previous);
+        data.extend_from_slice(&event.data);
+        previous = event.tick;
+    }
+    data.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+    file.extend_from_slice(b"MTrk");
+    file.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    file.extend_from_slice(&data);
+}
+
+/// MIDI variable-length quantity: 7 bits per byte, most significant first
+fn write_variable_length(data: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    data.extend(bytes.into_iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    fn note_ons(events: &[Event]) -> Vec<(u32, u8)> {
+        events
+            .iter()
+            .filter(|event| event.data[0] == 0x90)
+            .map(|event| (event.tick, event.data[1]))
+            .collect()
+    }
+
+    #[test]
+    fn test_variable_length() {
+        let mut data = Vec::new();
+        write_variable_length(&mut data, 0);
+        write_variable_length(&mut data, 0x80);
+        write_variable_length(&mut data, 0x0FFF_FFFF);
+        assert_eq!(data, [0x00, 0x81, 0x00, 0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn test_chord_events_follow_beats() {
+        let chart = parse_chart("(Am_G) 2x N.C. C fermata\n").unwrap();
+        let events = chord_events(&chart.playback(), TimeSignature::default());
+
+        let roots: Vec<(u32, u8)> = note_ons(&events)
+            .into_iter()
+            .filter(|&(_, key)| key < CHORD_OCTAVE as u8)
+            .collect();
+        // Am and G split each bar; the N.C. bar is silent
+        assert_eq!(roots, [(0, 57), (960, 55), (1920, 57), (2880, 55), (5760, 48)]);
+
+        // The fermata doubles the final bar
+        assert_eq!(events.last().unwrap().tick, 5760 + 2 * 1920);
+    }
+
+    #[test]
+    fn test_push_in_split_bar_stays_in_order() {
+        let chart = parse_chart("C_D_<E_F\n").unwrap();
+        let events = chord_events(&chart.playback(), TimeSignature { beats: 2, beat_type: 8 });
+
+        // Slots are a sixteenth apart, so the push stops at D rather than going ahead of it
+        let roots: Vec<(u32, u8)> = note_ons(&events)
+            .into_iter()
+            .filter(|&(_, key)| key < CHORD_OCTAVE as u8)
+            .collect();
+        assert_eq!(roots, [(0, 48), (120, 50), (120, 52), (360, 53)]);
+
+        // Every note-off follows its note-on
+        let mut held = [0i32; 128];
+        for event in &events {
+            match event.data[0] {
+                0x90 => held[event.data[1] as usize] += 1,
+                0x80 => held[event.data[1] as usize] -= 1,
+                _ => {}
+            }
+            assert!(held.iter().all(|&count| count >= 0));
+        }
+        assert!(held.iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn test_export_header_and_tempo() {
+        let chart = parse_chart("- <Tempo: 100 >3/4\nC\n").unwrap();
+        let file = export(&chart);
+
+        assert_eq!(&file[..4], b"MThd");
+        assert_eq!(&file[8..14], [0, 1, 0, 2, 0x01, 0xE0]);
+        // 600000 microseconds per quarter, then 3/4
+        assert_eq!(&file[22..36], [0x00, 0xFF, 0x51, 0x03, 0x09, 0x27, 0xC0, 0x00, 0xFF, 0x58, 0x04, 3, 2, 24]);
+
+        // Below 4 bpm the tempo is held at the slowest the event can state
+        let file = export(&parse_chart("- Tempo: 2\nC\n").unwrap());
+        assert_eq!(&file[23..29], [0xFF, 0x51, 0x03, 0xFF, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,8 @@
+// Exporters writing charts to other chart formats
+
+pub mod chordpro;
+pub mod ireal;
+pub mod latex;
+pub mod markdown;
+pub mod midi;
+pub mod musicxml;
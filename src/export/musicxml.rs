@@ -0,0 +1,325 @@
+// MusicXML export of chord progressions
+//
+// Each measure holds `<harmony>` symbols over slash notes (rests for `N.C.`), one note per
+// beat slot. Repeats, voltas and section headers follow the chart's measures; title,
+// composer, key, meter and tempo come from its header and text lines.
+
+use crate::model::{Beat, Chart, Chord, Key, Measure, Note, NoteName, TimeSignature};
+use std::fmt::Write;
+
+/// Divisions per quarter note: fine enough to split any common measure into 2, 3, 4 or 6 slots
+const DIVISIONS: u32 = 12;
+
+/// Write the chord lines of a chart as a single-part MusicXML score
+pub fn export(chart: &Chart) -> String {
+    let time = chart.time_signature().unwrap_or_default();
+    let mut xml = String::new();
+
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    xml.push_str("<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \"http://www.musicxml.org/dtds/partwise.dtd\">\n");
+    xml.push_str("<score-partwise version=\"4.0\">\n");
+    if let Some(title) = chart.title() {
+        let _ = writeln!(xml, "  <work><work-title>{}</work-title></work>", escape(&title));
+    }
+    if let Some(composer) = chart.subtitle() {
+        let _ = writeln!(
+            xml,
+            "  <identification><creator type=\"composer\">{}</creator></identification>",
+            escape(&composer)
+        );
+    }
+    xml.push_str("  <part-list>\n    <score-part id=\"P1\"><part-name>Chords</part-name></score-part>\n  </part-list>\n");
+    xml.push_str("  <part id=\"P1\">\n");
+
+    let measures = chart.measures();
+    let key = chart.key_or_first_chord();
+    if measures.is_empty() {
+        // A score needs at least one measure
+        xml.push_str("    <measure number=\"1\">\n");
+        write_attributes(&mut xml, key, time);
+        let _ = writeln!(
+            xml,
+            "      <note><rest measure=\"yes\"/><duration>{}</duration></note>",
+            measure_duration(time)
+        );
+        xml.push_str("    </measure>\n");
+    }
+    for (index, measure) in measures.iter().enumerate() {
+        let _ = writeln!(xml, "    <measure number=\"{}\">", index + 1);
+        if index == 0 {
+            write_attributes(&mut xml, key, time);
+            if let Some(tempo) = chart.tempo() {
+                let _ = writeln!(
+                    xml,
+                    "      <direction placement=\"above\"><direction-type><metronome><beat-unit>quarter</beat-unit><per-minute>{0}</per-minute></metronome></direction-type><sound tempo=\"{0}\"/></direction>",
+                    tempo
+                );
+            }
+        }
+        write_left_barline(&mut xml, measure);
+        if let Some(section) = &measure.section {
+            let _ = writeln!(
+                xml,
+                "      <direction placement=\"above\"><direction-type><rehearsal>{}</rehearsal></direction-type></direction>",
+                escape(section)
+            );
+        }
+        for annotation in &measure.annotations {
+            let _ = writeln!(
+                xml,
+                "      <direction placement=\"above\"><direction-type><words>{}</words></direction-type></direction>",
+                escape(annotation)
+            );
+        }
+        write_notes(&mut xml, measure, time);
+        write_right_barline(&mut xml, measure);
+        xml.push_str("    </measure>\n");
+    }
+
+    xml.push_str("  </part>\n</score-partwise>\n");
+    xml
+}
+
+/// Divisions, key (as stated, or from the first chord), time signature and clef for the first measure
+fn write_attributes(xml: &mut String, key: Option<Key>, time: TimeSignature) {
+    let _ = write!(xml, "      <attributes><divisions>{}</divisions>", DIVISIONS);
+    if let Some(key) = key {
+        let fifths = key_fifths(key.tonic) - if key.minor { 3 } else { 0 };
+        if (-7..=7).contains(&fifths) {
+            let mode = if key.minor { "minor" } else { "major" };
+            let _ = write!(xml, "<key><fifths>{}</fifths><mode>{}</mode></key>", fifths, mode);
+        }
+    }
+    let _ = writeln!(
+        xml,
+        "<time><beats>{}</beats><beat-type>{}</beat-type></time><clef><sign>G</sign><line>2</line></clef></attributes>",
+        time.beats, time.beat_type
+    );
+}
+
+fn write_left_barline(xml: &mut String, measure: &Measure) {
+    let ending = measure.ending.filter(|volta| volta.start);
+    if !measure.repeat_start && ending.is_none() {
+        return;
+    }
+    xml.push_str("      <barline location=\"left\">");
+    if measure.repeat_start {
+        xml.push_str("<bar-style>heavy-light</bar-style>");
+    }
+    if let Some(volta) = ending {
+        let _ = write!(xml, "<ending number=\"{}\" type=\"start\"/>", volta.number);
+    }
+    if measure.repeat_start {
+        xml.push_str("<repeat direction=\"forward\"/>");
+    }
+    xml.push_str("</barline>\n");
+}
+
+fn write_right_barline(xml: &mut String, measure: &Measure) {
+    let ending = measure.ending.filter(|volta| volta.stop);
+    if measure.repeat_end.is_none() && ending.is_none() {
+        return;
+    }
+    xml.push_str("      <barline location=\"right\">");
+    if measure.repeat_end.is_some() {
+        xml.push_str("<bar-style>light-heavy</bar-style>");
+    }
+    if let Some(volta) = ending {
+        // The last ending runs on instead of closing with a hook
+        let kind = if measure.repeat_end.is_some() { "stop" } else { "discontinue" };
+        let _ = write!(xml, "<ending number=\"{}\" type=\"{}\"/>", volta.number, kind);
+    }
+    if let Some(times) = measure.repeat_end {
+        if times > 2 {
+            let _ = write!(xml, "<repeat direction=\"backward\" times=\"{}\"/>", times);
+        } else {
+            xml.push_str("<repeat direction=\"backward\"/>");
+        }
+    }
+    xml.push_str("</barline>\n");
+}
+
+/// Harmony symbols and one slash note (or rest) per beat slot
+fn write_notes(xml: &mut String, measure: &Measure, time: TimeSignature) {
+    let total = measure_duration(time);
+    let beats = &measure.bar.beats;
+
+    if beats.is_empty() {
+        let _ = writeln!(xml, "      <note><rest measure=\"yes\"/><duration>{}</duration></note>", total);
+        return;
+    }
+
+    let slot = total / beats.len() as u32;
+    for (index, beat) in beats.iter().enumerate() {
+        let last = index + 1 == beats.len();
+        let duration = if last { total - slot * index as u32 } else { slot };
+        let fermata = if last && measure.bar.fermata {
+            "<notations><fermata type=\"upright\"/></notations>"
+        } else {
+            ""
+        };
+
+        match beat {
+            Beat::Chord(hit) => write_harmony(xml, &hit.chord),
+            Beat::NoChord => xml.push_str(
+                "      <harmony><root><root-step text=\"\">C</root-step></root><kind text=\"N.C.\">none</kind></harmony>\n",
+            ),
+            Beat::Empty => {}
+        }
+
+        if matches!(beat, Beat::NoChord) {
+            let _ = writeln!(
+                xml,
+                "      <note><rest/><duration>{}</duration>{}{}</note>",
+                duration,
+                note_type(duration),
+                fermata
+            );
+        } else {
+            let _ = writeln!(
+                xml,
+                "      <note><pitch><step>B</step><octave>4</octave></pitch><duration>{}</duration>{}<stem>none</stem><notehead>slash</notehead>{}</note>",
+                duration,
+                note_type(duration),
+                fermata
+            );
+        }
+    }
+}
+
+fn measure_duration(time: TimeSignature) -> u32 {
+    time.beats * DIVISIONS * 4 / time.beat_type
+}
+
+fn write_harmony(xml: &mut String, chord: &Chord) {
+    xml.push_str("      <harmony><root>");
+    write_step(xml, "root", chord.root);
+    let _ = write!(
+        xml,
+        "</root><kind text=\"{}\">{}</kind>",
+        escape(&chord.quality),
+        kind(chord)
+    );
+    if let Some(bass) = chord.bass {
+        xml.push_str("<bass>");
+        write_step(xml, "bass", bass);
+        xml.push_str("</bass>");
+    }
+    xml.push_str("</harmony>\n");
+}
+
+fn write_step(xml: &mut String, prefix: &str, note: Note) {
+    let _ = write!(xml, "<{0}-step>{1}</{0}-step>", prefix, note.name.as_char());
+    let alter = note.accidental.offset();
+    if alter != 0 {
+        let _ = write!(xml, "<{0}-alter>{1}</{0}-alter>", prefix, alter);
+    }
+}
+
+/// `<type>` (and `<dot/>`) for durations that are a plain or dotted note value
+fn note_type(duration: u32) -> &'static str {
+    match duration {
+        48 => "<type>whole</type>",
+        36 => "<type>half</type><dot/>",
+        24 => "<type>half</type>",
+        18 => "<type>quarter</type><dot/>",
+        12 => "<type>quarter</type>",
+        9 => "<type>eighth</type><dot/>",
+        6 => "<type>eighth</type>",
+        3 => "<type>16th</type>",
+        _ => "",
+    }
+}
+
+/// MusicXML chord kind for a quality, falling back to the triad when it is not a standard kind
+fn kind(chord: &Chord) -> &'static str {
+    match chord.quality.as_str() {
+        "" | "maj" => "major",
+        "m" | "min" | "-" => "minor",
+        "aug" | "+" => "augmented",
+        "dim" | "o" => "diminished",
+        "7" => "dominant",
+        "maj7" | "M7" | "Δ" | "Δ7" => "major-seventh",
+        "m7" | "min7" | "-7" => "minor-seventh",
+        "dim7" | "o7" => "diminished-seventh",
+        "aug7" | "+7" | "7#5" => "augmented-seventh",
+        "m7b5" | "ø" | "ø7" => "half-diminished",
+        "mmaj7" | "m(maj7)" | "mM7" => "major-minor",
+        "6" => "major-sixth",
+        "m6" => "minor-sixth",
+        "9" => "dominant-ninth",
+        "maj9" => "major-ninth",
+        "m9" => "minor-ninth",
+        "11" => "dominant-11th",
+        "maj11" => "major-11th",
+        "m11" => "minor-11th",
+        "13" => "dominant-13th",
+        "maj13" => "major-13th",
+        "m13" => "minor-13th",
+        "sus2" => "suspended-second",
+        "sus" | "sus4" => "suspended-fourth",
+        "5" => "power",
+        _ if is_minor(chord) => "minor",
+        _ => "major",
+    }
+}
+
+/// Whether the chord has a minor third
+fn is_minor(chord: &Chord) -> bool {
+    chord.intervals().contains(&(3, 3))
+}
+
+/// Position of a major key on the circle of fifths (C = 0, G = 1, F = -1)
+fn key_fifths(tonic: Note) -> i32 {
+    let natural = match tonic.name {
+        NoteName::F => -1,
+        NoteName::C => 0,
+        NoteName::G => 1,
+        NoteName::D => 2,
+        NoteName::A => 3,
+        NoteName::E => 4,
+        NoteName::B => 5,
+    };
+    natural + 7 * tonic.accidental.offset()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    #[test]
+    fn test_export_musicxml_structure() {
+        let chart = parse_chart("=== <>Blue Bossa\n== <>Kenny Dorham\n- >3/4\n= A\n(Cm7 Fm7_Bb7 1. Ebmaj7 2. N.C. fermata)\n").unwrap();
+        let xml = export(&chart);
+
+        assert!(xml.contains("<work-title>Blue Bossa</work-title>"));
+        assert!(xml.contains("<creator type=\"composer\">Kenny Dorham</creator>"));
+        assert!(xml.contains("<key><fifths>-3</fifths><mode>minor</mode></key>"));
+        assert!(xml.contains("<time><beats>3</beats><beat-type>4</beat-type></time>"));
+        assert!(xml.contains("<rehearsal>A</rehearsal>"));
+        assert_eq!(xml.matches("<measure number=").count(), 4);
+        assert!(xml.contains("<bar-style>heavy-light</bar-style><repeat direction=\"forward\"/>"));
+        assert!(xml.contains("<ending number=\"1\" type=\"stop\"/><repeat direction=\"backward\"/>"));
+        assert!(xml.contains("<ending number=\"2\" type=\"discontinue\"/>"));
+        assert!(xml.contains("<root-step>B</root-step><root-alter>-1</root-alter></root><kind text=\"7\">dominant</kind>"));
+        assert!(xml.contains("<kind text=\"maj7\">major-seventh</kind>"));
+        // Two chords split a 3/4 bar into two slots of 18 divisions
+        assert!(xml.contains("<duration>18</duration><type>quarter</type><dot/>"));
+        assert!(xml.contains("<rest/><duration>36</duration><type>half</type><dot/><notations><fermata"));
+    }
+
+    #[test]
+    fn test_key_from_key_line() {
+        let xml = export(&parse_chart("=== <>Song\n= >Key: Gm\nAm7b5 D7 Gm\n").unwrap());
+        assert!(xml.contains("<key><fifths>-2</fifths><mode>minor</mode></key>"));
+        assert!(!xml.contains("<rehearsal>"));
+    }
+}
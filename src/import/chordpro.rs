@@ -0,0 +1,201 @@
+// ChordPro import
+//
+// Metadata directives become header lines, section environments become `=` section
+// headers and inline `[Chord]` markers become chord lines with one bar per chord.
+// Lyrics are not carried over (charts describe form and harmony, not words), with a warning
+// for each line that had some.
+
+use super::{Import, ImportWarning};
+use crate::model::{Bar, Beat, Chart, Chord, ChordHit, ChordItem, Line, LineLevel, TextSpan, TextStyle};
+
+/// Convert a ChordPro document into a chart
+pub fn import(input: &str) -> Import {
+    let mut lines = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (index, source) in input.lines().enumerate() {
+        let number = index + 1;
+        let source = source.trim();
+
+        if source.is_empty() || source.starts_with('#') {
+            continue;
+        }
+
+        if let Some(directive) = source.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            let (name, value) = match directive.split_once([':', ' ']) {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => (directive.trim(), ""),
+            };
+            match directive_line(&name.to_ascii_lowercase(), value) {
+                Directive::Line(line) => lines.push(line),
+                Directive::Ignored => {}
+                Directive::Unsupported => warnings.push(ImportWarning::new(
+                    number,
+                    format!("unsupported directive '{{{}}}'", name),
+                )),
+            }
+            continue;
+        }
+
+        let (beats, lyrics) = chord_beats(source, number, &mut warnings);
+        if lyrics {
+            warnings.push(ImportWarning::new(number, "lyrics are not imported"));
+        }
+        if !beats.is_empty() {
+            let items = beats.into_iter().map(|beat| ChordItem::Bar(Bar::new(vec![beat])));
+            lines.push(Line::chords(items.collect()));
+        }
+    }
+
+    Import {
+        chart: Chart::new(lines),
+        warnings,
+    }
+}
+
+enum Directive {
+    Line(Line),
+    /// Understood but produces nothing (e.g. the end of a section)
+    Ignored,
+    Unsupported,
+}
+
+fn directive_line(name: &str, value: &str) -> Directive {
+    let centered = |level, text: &str| Line::new(level, vec![], vec![TextSpan::plain(text)], vec![]);
+    let right = |label: &str| {
+        Line::new(
+            LineLevel::Header3,
+            vec![],
+            vec![],
+            vec![TextSpan::plain(format!("{}: {}", label, value))],
+        )
+    };
+    let comment = |style| Line::new(LineLevel::Text, vec![TextSpan::new(value, style)], vec![], vec![]);
+
+    let line = match name {
+        "title" | "t" => centered(LineLevel::Header1, value),
+        "subtitle" | "st" | "artist" => centered(LineLevel::Header2, value),
+        "key" => right("Key"),
+        "tempo" => right("Tempo"),
+        "time" => right("Time"),
+        "comment" | "c" | "highlight" => comment(TextStyle::Normal),
+        "comment_italic" | "ci" => comment(TextStyle::Italic),
+        "comment_box" | "cb" => comment(TextStyle::Bold),
+        _ => match section_name(name) {
+            Some(section) => {
+                let label = if value.is_empty() { section } else { value };
+                Line::new(LineLevel::Header3, vec![TextSpan::plain(label)], vec![], vec![])
+            }
+            None if is_section_end(name) => return Directive::Ignored,
+            None => return Directive::Unsupported,
+        },
+    };
+    Directive::Line(line)
+}
+
+/// Section label for a `start_of_*` directive (or its short form)
+fn section_name(name: &str) -> Option<&'static str> {
+    match name {
+        "start_of_chorus" | "soc" => Some("Chorus"),
+        "start_of_verse" | "sov" => Some("Verse"),
+        "start_of_bridge" | "sob" => Some("Bridge"),
+        "start_of_tab" | "sot" => Some("Tab"),
+        "start_of_grid" | "sog" => Some("Grid"),
+        _ => None,
+    }
+}
+
+fn is_section_end(name: &str) -> bool {
+    name.starts_with("end_of_") || matches!(name, "eoc" | "eov" | "eob" | "eot" | "eog")
+}
+
+/// Chords from the inline `[Chord]` markers of a line, one beat each.
+///
+/// Also returns whether the line had lyrics besides the chords.
+fn chord_beats(source: &str, number: usize, warnings: &mut Vec<ImportWarning>) -> (Vec<Beat>, bool) {
+    let mut beats = Vec::new();
+    let mut lyrics = false;
+    let mut rest = source;
+
+    while let Some(open) = rest.find('[') {
+        lyrics |= !rest[..open].trim().is_empty();
+        let Some(close) = rest[open..].find(']') else {
+            break;
+        };
+        let symbol = rest[open + 1..open + close].trim();
+        rest = &rest[open + close + 1..];
+
+        let beat = match symbol {
+            "N.C." | "NC" => Beat::NoChord,
+            _ => match symbol.parse::<Chord>() {
+                Ok(chord) => Beat::Chord(ChordHit::plain(chord)),
+                Err(error) => {
+                    warnings.push(ImportWarning::new(number, error.to_string()));
+                    continue;
+                }
+            },
+        };
+        beats.push(beat);
+    }
+    lyrics |= !rest.trim().is_empty();
+
+    (beats, lyrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SONG: &str = "\
+{title: Amazing Grace}
+{artist: John Newton}
+{key: G}
+# traditional
+{start_of_verse: Verse 1}
+A[G]mazing [G7]grace, how [C]sweet the [G]sound
+{end_of_verse}
+{soc}
+[Em][D/F#] [X9]
+{eoc}
+{c: Slowly}
+{new_page}
+";
+
+    #[test]
+    fn test_import_directives_and_chords() {
+        let import = import(SONG);
+        let lines = &import.chart.lines;
+
+        assert_eq!(lines[0].level, LineLevel::Header1);
+        assert_eq!(lines[0].center[0].text, "Amazing Grace");
+        assert_eq!(lines[1].level, LineLevel::Header2);
+        assert_eq!(lines[2].right[0].text, "Key: G");
+        assert_eq!(lines[3].left[0].text, "Verse 1");
+
+        let chords: Vec<String> = lines[4].chords.iter().map(|item| item.to_string()).collect();
+        assert_eq!(chords, ["G", "G7", "C", "G"]);
+
+        assert_eq!(lines[5].left[0].text, "Chorus");
+        assert_eq!(lines[6].chords.len(), 2);
+        assert_eq!(lines[7].level, LineLevel::Text);
+        assert_eq!(lines.len(), 8);
+    }
+
+    #[test]
+    fn test_import_warnings() {
+        let warnings: Vec<String> = import(SONG).warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "line 6: lyrics are not imported",
+                "line 9: invalid chord symbol 'X9'",
+                "line 12: unsupported directive '{new_page}'",
+            ]
+        );
+
+        // Every lyric line is reported, not just the first
+        let lyrics = import("[C]Was blind\n[G]now I [D]see\n");
+        assert_eq!(lyrics.warnings.len(), 2);
+        assert_eq!(lyrics.warnings[1].to_string(), "line 2: lyrics are not imported");
+    }
+}
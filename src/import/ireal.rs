@@ -0,0 +1,530 @@
+// iReal Pro URL import
+//
+// Decodes `irealb://` (obfuscated) and `irealbook://` (plain) song URLs. Sections become
+// `=` headers, `{ }` repeats become repeat groups with their `N1`/`N2` endings, and each
+// bar's chords share its beat slots evenly.
+
+use super::{Import, ImportWarning};
+use crate::model::{
+    Bar, Beat, Chart, Chord, ChordHit, ChordItem, Line, LineLevel, RepeatGroup, TextSpan,
+};
+use miette::Diagnostic;
+use thiserror::Error;
+
+/// Prefix marking an obfuscated `irealb://` chord progression
+pub(crate) const MUSIC_PREFIX: &str = "1r34LbKcu7";
+
+/// Bars per chord line when a section runs long
+const BARS_PER_LINE: usize = 4;
+
+/// Error decoding an iReal Pro URL
+#[derive(Debug, Error, Diagnostic)]
+pub enum IRealError {
+    #[error("not an iReal Pro URL (expected irealb:// or irealbook://)")]
+    #[diagnostic(code(import::ireal::scheme))]
+    Scheme,
+
+    #[error("malformed iReal Pro song: {0}")]
+    #[diagnostic(code(import::ireal::malformed))]
+    Malformed(String),
+}
+
+/// Song fields shared by both URL flavours
+struct Song {
+    title: String,
+    composer: String,
+    style: String,
+    key: String,
+    music: String,
+}
+
+/// Convert the first song of an iReal Pro URL into a chart
+pub fn import(url: &str) -> Result<Import, IRealError> {
+    let url = url.trim();
+    let (song, more) = if let Some(body) = url.strip_prefix("irealb://") {
+        parse_irealb(&percent_decode(body)?)?
+    } else if let Some(body) = url.strip_prefix("irealbook://") {
+        parse_irealbook(&percent_decode(body)?)?
+    } else {
+        return Err(IRealError::Scheme);
+    };
+
+    let mut warnings = Vec::new();
+    if more {
+        warnings.push(ImportWarning::new(1, "only the first song of the playlist is imported"));
+    }
+
+    let mut lines = vec![Line::new(
+        LineLevel::Header1,
+        vec![],
+        vec![TextSpan::plain(&song.title)],
+        vec![],
+    )];
+    if !song.composer.is_empty() {
+        lines.push(Line::new(
+            LineLevel::Header2,
+            vec![],
+            vec![TextSpan::plain(&song.composer)],
+            vec![],
+        ));
+    }
+
+    let mut reader = MusicReader::new(&mut warnings);
+    reader.read(&song.music);
+    let (time, body) = reader.finish();
+
+    let details: Vec<String> = [
+        (!song.key.is_empty()).then(|| format!("Key: {}", from_ireal_key(&song.key))),
+        time.map(|(beats, beat_type)| format!("Time: {}/{}", beats, beat_type)),
+        (!song.style.is_empty()).then(|| format!("Style: {}", song.style)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    for detail in details {
+        lines.push(Line::new(LineLevel::Header3, vec![], vec![], vec![TextSpan::plain(detail)]));
+    }
+    lines.extend(body);
+
+    Ok(Import {
+        chart: Chart::new(lines),
+        warnings,
+    })
+}
+
+/// `Title=Composer==Style=Key==Music=...`, songs separated by `===`
+fn parse_irealb(body: &str) -> Result<(Song, bool), IRealError> {
+    let mut songs = body.split("===").filter(|song| !song.is_empty());
+    let first = songs.next().ok_or_else(|| IRealError::Malformed("no songs".to_string()))?;
+    let fields: Vec<&str> = first.split('=').collect();
+    if fields.len() < 7 {
+        return Err(IRealError::Malformed("missing song fields".to_string()));
+    }
+
+    let music = fields[6]
+        .strip_prefix(MUSIC_PREFIX)
+        .ok_or_else(|| IRealError::Malformed("chord progression is not obfuscated".to_string()))?;
+    let music = scramble(music)
+        .replace("XyQ", "   ")
+        .replace("LZ", " |")
+        .replace("Kcl", "| x");
+
+    // A playlist ends with its own name after the last song
+    let more = songs.count() > 1;
+    Ok((
+        Song {
+            title: fields[0].to_string(),
+            composer: fields[1].to_string(),
+            style: fields[3].to_string(),
+            key: fields[4].to_string(),
+            music,
+        },
+        more,
+    ))
+}
+
+/// `Title=Composer=Style=Key=n=Music`, songs separated by `=`-joined runs of six fields
+fn parse_irealbook(body: &str) -> Result<(Song, bool), IRealError> {
+    let fields: Vec<&str> = body.split('=').collect();
+    if fields.len() < 6 {
+        return Err(IRealError::Malformed("missing song fields".to_string()));
+    }
+    Ok((
+        Song {
+            title: fields[0].to_string(),
+            composer: fields[1].to_string(),
+            style: fields[2].to_string(),
+            key: fields[3].to_string(),
+            music: fields[5].to_string(),
+        },
+        fields.len() > 6,
+    ))
+}
+
+/// Builds chord lines while walking the progression
+struct MusicReader<'w> {
+    warnings: &'w mut Vec<ImportWarning>,
+    lines: Vec<Line>,
+    /// Items of the chord line being built
+    items: Vec<ChordItem>,
+    /// Open (or just closed, awaiting further endings) repeat group
+    group: Option<Vec<ChordItem>>,
+    group_closed: bool,
+    /// Beat slots of the bar being built
+    beats: Vec<Beat>,
+    repeat_bar: bool,
+    fermata: bool,
+    bars_on_line: usize,
+    time: Option<(u32, u32)>,
+}
+
+impl<'w> MusicReader<'w> {
+    fn new(warnings: &'w mut Vec<ImportWarning>) -> Self {
+        Self {
+            warnings,
+            lines: Vec::new(),
+            items: Vec::new(),
+            group: None,
+            group_closed: false,
+            beats: Vec::new(),
+            repeat_bar: false,
+            fermata: false,
+            bars_on_line: 0,
+            time: None,
+        }
+    }
+
+    fn read(&mut self, music: &str) {
+        let mut rest = music;
+        while let Some(c) = rest.chars().next() {
+            let after = &rest[c.len_utf8()..];
+            rest = match c {
+                ' ' | ',' | 's' | 'l' | 'Y' | 'U' => after,
+                '|' | '[' => {
+                    self.end_bar();
+                    after
+                }
+                ']' | 'Z' => {
+                    self.end_bar();
+                    self.close_group();
+                    after
+                }
+                '{' => {
+                    self.end_bar();
+                    self.close_group();
+                    self.group = Some(Vec::new());
+                    after
+                }
+                '}' => {
+                    self.end_bar();
+                    self.group_closed = true;
+                    after
+                }
+                'T' => {
+                    let digits: String = after.chars().take(2).collect();
+                    self.time = match digits.as_str() {
+                        "12" => Some((12, 8)),
+                        _ => digits
+                            .chars()
+                            .filter_map(|d| d.to_digit(10))
+                            .collect::<Vec<_>>()
+                            .try_into()
+                            .ok()
+                            .map(|[beats, beat_type]: [u32; 2]| (beats, beat_type)),
+                    };
+                    &after[digits.len()..]
+                }
+                '*' => {
+                    let mark = after.chars().next().unwrap_or('A');
+                    self.section(match mark {
+                        'i' => "Intro".to_string(),
+                        'V' => "Verse".to_string(),
+                        mark => mark.to_string(),
+                    });
+                    &after[mark.len_utf8().min(after.len())..]
+                }
+                'N' => {
+                    // The ending number is one ASCII digit; anything else is left to read on
+                    let (number, rest) = match after.chars().next().and_then(|d| d.to_digit(10)) {
+                        Some(number) => (number, &after[1..]),
+                        None => (1, after),
+                    };
+                    self.ending(number);
+                    rest
+                }
+                '<' => {
+                    let end = after.find('>').unwrap_or(after.len());
+                    let text = after[..end].trim();
+                    if !text.is_empty() {
+                        self.item(ChordItem::Annotation(text.to_string()));
+                    }
+                    &after[(end + 1).min(after.len())..]
+                }
+                '(' => {
+                    let end = after.find(')').unwrap_or(after.len());
+                    self.warn(format!("alternate chord '{}' is not imported", &after[..end]));
+                    &after[(end + 1).min(after.len())..]
+                }
+                'x' => {
+                    self.repeat_bar = true;
+                    after
+                }
+                'r' => {
+                    self.warn("two-bar repeat is not imported");
+                    after
+                }
+                'n' => {
+                    self.beats.push(Beat::NoChord);
+                    after
+                }
+                'p' => {
+                    self.beats.push(Beat::Empty);
+                    after
+                }
+                'W' => {
+                    // Invisible root: only the slash bass changes, so nothing new is struck
+                    self.beats.push(Beat::Empty);
+                    let end = after.find([' ', ',', '|']).unwrap_or(after.len());
+                    &after[end..]
+                }
+                'f' => {
+                    self.fermata = true;
+                    after
+                }
+                'S' => {
+                    self.item(ChordItem::Annotation("Segno".to_string()));
+                    after
+                }
+                'Q' => {
+                    self.item(ChordItem::Annotation("Coda".to_string()));
+                    after
+                }
+                'A'..='G' => {
+                    let end = rest
+                        .find(|c: char| " ,|[]{}<(ZxnpfNT*".contains(c))
+                        .unwrap_or(rest.len());
+                    self.chord(&rest[..end]);
+                    &rest[end..]
+                }
+                other => {
+                    self.warn(format!("unknown symbol '{}'", other));
+                    after
+                }
+            };
+        }
+    }
+
+    fn chord(&mut self, symbol: &str) {
+        let (chord, bass) = match symbol.split_once('/') {
+            Some((chord, bass)) => (chord, Some(bass)),
+            None => (symbol, None),
+        };
+        let root_length = chord
+            .char_indices()
+            .nth(1)
+            .filter(|&(_, c)| c == 'b' || c == '#')
+            .map_or(1, |_| 2)
+            .min(chord.len());
+        let mut text = format!("{}{}", &chord[..root_length], from_ireal_quality(&chord[root_length..]));
+        if let Some(bass) = bass {
+            text.push('/');
+            text.push_str(bass);
+        }
+
+        match text.parse::<Chord>() {
+            Ok(chord) => self.beats.push(Beat::Chord(ChordHit::plain(chord))),
+            Err(error) => self.warn(error.to_string()),
+        }
+    }
+
+    fn end_bar(&mut self) {
+        let item = if self.repeat_bar && self.beats.is_empty() {
+            ChordItem::RepeatBar
+        } else if self.beats.is_empty() {
+            return;
+        } else {
+            let mut bar = Bar::new(std::mem::take(&mut self.beats));
+            bar.fermata = self.fermata;
+            ChordItem::Bar(bar)
+        };
+        self.repeat_bar = false;
+        self.fermata = false;
+
+        // Anything after a closed group other than another ending ends the group
+        if self.group_closed {
+            self.close_group();
+        }
+        self.item(item);
+        self.bars_on_line += 1;
+        if self.group.is_none() && self.bars_on_line >= BARS_PER_LINE {
+            self.break_line();
+        }
+    }
+
+    fn item(&mut self, item: ChordItem) {
+        match &mut self.group {
+            Some(group) => group.push(item),
+            None => self.items.push(item),
+        }
+    }
+
+    fn ending(&mut self, number: u32) {
+        self.end_bar();
+        if self.group.is_some() {
+            // Later endings follow the closing brace but still belong to the group
+            self.group_closed = false;
+            self.item(ChordItem::Ending(number));
+        } else {
+            self.item(ChordItem::Ending(number));
+        }
+    }
+
+    fn close_group(&mut self) {
+        self.group_closed = false;
+        if let Some(items) = self.group.take() {
+            self.items.push(ChordItem::Group(RepeatGroup { items, times: None }));
+        }
+    }
+
+    fn section(&mut self, name: String) {
+        self.end_bar();
+        self.close_group();
+        self.break_line();
+        self.lines.push(Line::new(LineLevel::Header3, vec![TextSpan::plain(name)], vec![], vec![]));
+    }
+
+    fn break_line(&mut self) {
+        self.bars_on_line = 0;
+        if !self.items.is_empty() {
+            self.lines.push(Line::chords(std::mem::take(&mut self.items)));
+        }
+    }
+
+    fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(ImportWarning::new(1, message));
+    }
+
+    fn finish(mut self) -> (Option<(u32, u32)>, Vec<Line>) {
+        self.end_bar();
+        self.close_group();
+        self.break_line();
+        (self.time, self.lines)
+    }
+}
+
+/// iReal chord quality to the chord-script spelling (`-7` → `m7`, `^7` → `maj7`, `h7` → `m7b5`)
+fn from_ireal_quality(quality: &str) -> String {
+    if let Some(rest) = quality.strip_prefix("h7").or(quality.strip_prefix('h')) {
+        format!("m7b5{}", rest)
+    } else if let Some(rest) = quality.strip_prefix("-^") {
+        format!("mmaj{}", if rest.is_empty() { "7" } else { rest })
+    } else if let Some(rest) = quality.strip_prefix('-') {
+        format!("m{}", rest)
+    } else if let Some(rest) = quality.strip_prefix('^') {
+        format!("maj{}", if rest.is_empty() { "7" } else { rest })
+    } else if let Some(rest) = quality.strip_prefix('o') {
+        format!("dim{}", rest)
+    } else if let Some(rest) = quality.strip_prefix('+') {
+        format!("aug{}", rest)
+    } else {
+        quality.to_string()
+    }
+}
+
+/// iReal key (`Eb`, `C-`) to a readable one (`Eb`, `Cm`)
+fn from_ireal_key(key: &str) -> String {
+    match key.strip_suffix('-') {
+        Some(tonic) => format!("{}m", tonic),
+        None => key.to_string(),
+    }
+}
+
+/// iReal's chord progression obfuscation. It swaps characters within 50-character blocks
+/// and is its own inverse, so the same function encodes and decodes.
+pub(crate) fn scramble(music: &str) -> String {
+    let chars: Vec<char> = music.chars().collect();
+    let mut output = String::with_capacity(music.len());
+    let mut rest = &chars[..];
+    while rest.len() > 50 {
+        let (block, remaining) = rest.split_at(50);
+        rest = remaining;
+        if rest.len() < 2 {
+            output.extend(block);
+        } else {
+            let mut swapped = block.to_vec();
+            for i in (0..5).chain(10..24) {
+                swapped[i] = block[49 - i];
+                swapped[49 - i] = block[i];
+            }
+            output.extend(swapped);
+        }
+    }
+    output.extend(rest);
+    output
+}
+
+fn percent_decode(text: &str) -> Result<String, IRealError> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = text
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| IRealError::Malformed("invalid percent escape".to_string()))?;
+            decoded.push(hex);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| IRealError::Malformed("invalid UTF-8".to_string()))
+}
+
+pub(crate) fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'=' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord_lines(import: &Import) -> Vec<String> {
+        import
+            .chart
+            .lines
+            .iter()
+            .map(|line| match line.level {
+                LineLevel::Chords => line.chords.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(" "),
+                _ => format!("= {}", line.left.iter().chain(&line.center).chain(&line.right).map(|span| span.text.as_str()).collect::<String>()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scramble_is_an_involution() {
+        let music: String = (0..120).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+        let scrambled = scramble(&music);
+        assert_ne!(scrambled, music);
+        assert_eq!(scramble(&scrambled), music);
+    }
+
+    #[test]
+    fn test_import_irealbook() {
+        let url = "irealbook://Blue%20Bossa=Dorham%20Kenny=Bossa%20Nova=C-=n=T34*A{C-7 p |x |F-7 Bb7 |N1Eb^7 }N2Dh7 G7b9 Z";
+        let import = import(url).unwrap();
+
+        assert_eq!(
+            chord_lines(&import),
+            [
+                "= Blue Bossa",
+                "= Dorham Kenny",
+                "= Key: Cm",
+                "= Time: 3/4",
+                "= Style: Bossa Nova",
+                "= A",
+                "(Cm7, % Fm7_Bb7 1. Ebmaj7 2. Dm7b5_G7b9)",
+            ]
+        );
+        assert!(import.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_import_rejects_other_schemes() {
+        assert!(matches!(import("https://example.com"), Err(IRealError::Scheme)));
+        assert!(matches!(import("irealb://Song=Me"), Err(IRealError::Malformed(_))));
+        // Stray text after an ending mark is read on, even when it is not ASCII
+        assert!(import("irealbook://T=C=S=C=n=[C7 Né D7 ]").is_ok());
+    }
+}
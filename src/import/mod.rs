@@ -0,0 +1,37 @@
+// Importers converting other chart formats into the chord-script model
+
+pub mod chordpro;
+pub mod ireal;
+
+use crate::model::Chart;
+use std::fmt;
+
+/// Result of importing a foreign chart: the converted chart plus anything that was lost
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub chart: Chart,
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Something in the source that could not be carried over into the chart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportWarning {
+    /// One-based line number in the source
+    pub line: usize,
+    pub message: String,
+}
+
+impl ImportWarning {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ImportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
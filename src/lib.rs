@@ -1,5 +1,15 @@
 // chord-script library root
 
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod export;
+pub mod import;
+#[cfg(feature = "lsp")]
+pub mod lsp;
 pub mod model;
 pub mod parser;
+#[cfg(feature = "python")]
+mod python;
 pub mod render;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,474 @@
+// Language server for .chords files
+
+use crate::model::{Chord, LineLevel};
+use crate::parser::cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, SyntaxTree};
+use crate::parser::{parse_cst, ParseError};
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest, HoverRequest, Request as LspRequest, SemanticTokensFullRequest,
+};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse, Hover, HoverContents,
+    HoverProviderCapability, MarkupContent, MarkupKind, NumberOrString, OneOf, Position,
+    PublishDiagnosticsParams, Range, SemanticToken, SemanticTokenModifier, SemanticTokenType,
+    SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensResult, SemanticTokensServerCapabilities, ServerCapabilities, SymbolKind,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Converts between byte offsets and LSP positions (UTF-16 columns)
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    /// LSP position of a byte offset
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Byte offset of an LSP position, clamped to the end of its line
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut units = 0;
+        for (index, c) in self.text[start..].char_indices() {
+            if c == '\n' || units >= position.character as usize {
+                return start + index;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    pub fn range(&self, span: &std::ops::Range<usize>) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+/// Diagnostics for a document: at most one parse error
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    match parse_cst(text) {
+        Ok(_) => Vec::new(),
+        Err(error) => vec![parse_error_diagnostic(text, &error)],
+    }
+}
+
+fn parse_error_diagnostic(text: &str, error: &ParseError) -> Diagnostic {
+    Diagnostic {
+        range: LineIndex::new(text).range(&error.span()),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String("parser::parse_error".to_string())),
+        source: Some("chord-script".to_string()),
+        message: error.help().to_string(),
+        ..Diagnostic::default()
+    }
+}
+
+const TOKEN_TYPES: [SemanticTokenType; 6] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::STRING,
+];
+
+const BOLD: u32 = 1;
+const ITALIC: u32 = 1 << 1;
+
+/// Semantic token legend advertised by the server
+pub fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: vec![
+            SemanticTokenModifier::new("bold"),
+            SemanticTokenModifier::new("italic"),
+        ],
+    }
+}
+
+fn token_type(kind: SyntaxKind) -> Option<SemanticTokenType> {
+    match kind {
+        SyntaxKind::LevelMarker | SyntaxKind::NoChord | SyntaxKind::Fermata => {
+            Some(SemanticTokenType::KEYWORD)
+        }
+        SyntaxKind::AlignMarker
+        | SyntaxKind::EmphasisMarker
+        | SyntaxKind::EmptyBeat
+        | SyntaxKind::HeldBeat
+        | SyntaxKind::BeatSeparator
+        | SyntaxKind::PushMarker
+        | SyntaxKind::AccentMarker
+        | SyntaxKind::GhostMarker
+        | SyntaxKind::RepeatBar
+        | SyntaxKind::GroupOpen
+        | SyntaxKind::GroupClose => Some(SemanticTokenType::OPERATOR),
+        SyntaxKind::Chord => Some(SemanticTokenType::TYPE),
+        SyntaxKind::RepeatCount | SyntaxKind::Ending => Some(SemanticTokenType::NUMBER),
+        SyntaxKind::Annotation => Some(SemanticTokenType::COMMENT),
+        _ => None,
+    }
+}
+
+/// Semantic tokens for a document, delta-encoded as the protocol requires
+pub fn semantic_tokens(text: &str) -> Vec<SemanticToken> {
+    let Ok(tree) = parse_cst(text) else {
+        return Vec::new();
+    };
+
+    let mut classified = Vec::new();
+    classify(&tree.root, 0, &mut classified);
+
+    let index = LineIndex::new(text);
+    let mut tokens = Vec::new();
+    let mut previous = Position::new(0, 0);
+    for (token, token_type, modifiers) in classified {
+        let start = index.position(token.span.start);
+        let delta_start = if start.line == previous.line {
+            start.character - previous.character
+        } else {
+            start.character
+        };
+        tokens.push(SemanticToken {
+            delta_line: start.line - previous.line,
+            delta_start,
+            length: token.text.encode_utf16().count() as u32,
+            token_type: TOKEN_TYPES.iter().position(|t| *t == token_type).unwrap_or(0) as u32,
+            token_modifiers_bitset: modifiers,
+        });
+        previous = start;
+    }
+    tokens
+}
+
+fn classify<'a>(
+    node: &'a SyntaxNode,
+    modifiers: u32,
+    out: &mut Vec<(&'a SyntaxToken, SemanticTokenType, u32)>,
+) {
+    // Emphasized text inherits bold/italic modifiers from its span's markers
+    let modifiers = if node.kind == SyntaxKind::Span {
+        match node.child_tokens().find(|t| t.kind == SyntaxKind::EmphasisMarker) {
+            Some(marker) if marker.text.len() == 3 => BOLD | ITALIC,
+            Some(marker) if marker.text.len() == 2 => BOLD,
+            Some(_) => ITALIC,
+            None => modifiers,
+        }
+    } else {
+        modifiers
+    };
+
+    for child in &node.children {
+        match child {
+            SyntaxElement::Node(child) => classify(child, modifiers, out),
+            SyntaxElement::Token(token) if token.kind == SyntaxKind::Text => {
+                if modifiers != 0 && !token.text.trim().is_empty() {
+                    out.push((token, SemanticTokenType::STRING, modifiers));
+                }
+            }
+            SyntaxElement::Token(token) => {
+                if let Some(token_type) = token_type(token.kind) {
+                    out.push((token, token_type, 0));
+                }
+            }
+        }
+    }
+}
+
+fn section_rank(level: LineLevel) -> Option<usize> {
+    match level {
+        LineLevel::Header1 => Some(1),
+        LineLevel::Header2 => Some(2),
+        LineLevel::Header3 => Some(3),
+        LineLevel::Text | LineLevel::Chords => None,
+    }
+}
+
+/// Header lines as nested document symbols; each section extends to the next header of equal or higher level
+pub fn document_symbols(text: &str) -> Vec<DocumentSymbol> {
+    let Ok(tree) = parse_cst(text) else {
+        return Vec::new();
+    };
+    let index = LineIndex::new(text);
+    let chart = tree.to_chart();
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<(usize, DocumentSymbol)> = Vec::new();
+
+    for (node, line) in tree.lines().zip(&chart.lines) {
+        let end = index.position(node.span.end);
+        if let Some(rank) = section_rank(line.level) {
+            while stack.last().is_some_and(|(open, _)| *open >= rank) {
+                close_section(&mut stack, &mut roots);
+            }
+
+            let name = [&line.left, &line.center, &line.right]
+                .iter()
+                .flat_map(|column| column.iter())
+                .map(|span| span.text.as_str())
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let range = index.range(&node.span);
+            let kind = match rank {
+                1 => SymbolKind::FILE,
+                2 => SymbolKind::NAMESPACE,
+                _ => SymbolKind::MODULE,
+            };
+
+            #[allow(deprecated)]
+            let symbol = DocumentSymbol {
+                name: if name.is_empty() { "(untitled)".to_string() } else { name },
+                detail: None,
+                kind,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: None,
+            };
+            stack.push((rank, symbol));
+        }
+
+        for (_, symbol) in stack.iter_mut() {
+            symbol.range.end = end;
+        }
+    }
+
+    while !stack.is_empty() {
+        close_section(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn close_section(stack: &mut Vec<(usize, DocumentSymbol)>, roots: &mut Vec<DocumentSymbol>) {
+    if let Some((_, symbol)) = stack.pop() {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(symbol),
+            None => roots.push(symbol),
+        }
+    }
+}
+
+/// Hover for the chord symbol under the cursor, listing its notes
+pub fn hover(text: &str, position: Position) -> Option<Hover> {
+    let tree = parse_cst(text).ok()?;
+    let index = LineIndex::new(text);
+    let offset = index.offset(position);
+
+    let token = chord_token_at(&tree, offset)?;
+    let chord: Chord = token.text.parse().ok()?;
+    let notes = chord
+        .notes()
+        .iter()
+        .map(|note| note.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("**{}**\n\n{}", chord, notes),
+        }),
+        range: Some(index.range(&token.span)),
+    })
+}
+
+fn chord_token_at(tree: &SyntaxTree, offset: usize) -> Option<&SyntaxToken> {
+    tree.root
+        .tokens()
+        .into_iter()
+        .find(|token| token.kind == SyntaxKind::Chord && token.span.start <= offset && offset <= token.span.end)
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: semantic_tokens_legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+type ServerResult<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Run the language server over stdin/stdout until the client shuts it down
+pub fn run() -> ServerResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    Server::default().main_loop(&connection)?;
+    // The writer thread only finishes once the connection's sender is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// Open documents, kept in sync with the client
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Uri, String>,
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> ServerResult<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    let uri = self.handle_notification(notification).unwrap_or_else(|error| {
+                        // Notifications have no reply, so a malformed one is logged and dropped
+                        eprintln!("chord-script lsp: ignoring notification: {}", error);
+                        None
+                    });
+                    if let Some(uri) = uri {
+                        let diagnostics = self
+                            .documents
+                            .get(&uri)
+                            .map(|text| diagnostics(text))
+                            .unwrap_or_default();
+                        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+                        connection.sender.send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_string(),
+                            params,
+                        )))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&self, uri: &Uri) -> &str {
+        self.documents.get(uri).map(String::as_str).unwrap_or("")
+    }
+
+    /// Answer a request; malformed params get an `InvalidParams` error reply
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        self.dispatch_request(request)
+            .unwrap_or_else(|error| Response::new_err(id, lsp_server::ErrorCode::InvalidParams as i32, error))
+    }
+
+    fn dispatch_request(&self, request: Request) -> Result<Response, String> {
+        let request = match cast_request::<SemanticTokensFullRequest>(request) {
+            Ok((id, params)) => {
+                let data = semantic_tokens(self.text(&params.text_document.uri));
+                let result = SemanticTokensResult::Tokens(SemanticTokens {
+                    result_id: None,
+                    data,
+                });
+                return Ok(Response::new_ok(id, result));
+            }
+            Err(request) => request?,
+        };
+
+        let request = match cast_request::<DocumentSymbolRequest>(request) {
+            Ok((id, params)) => {
+                let symbols = document_symbols(self.text(&params.text_document.uri));
+                return Ok(Response::new_ok(id, DocumentSymbolResponse::Nested(symbols)));
+            }
+            Err(request) => request?,
+        };
+
+        let request = match cast_request::<HoverRequest>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position_params;
+                let result = hover(self.text(&position.text_document.uri), position.position);
+                return Ok(Response::new_ok(id, result));
+            }
+            Err(request) => request?,
+        };
+
+        Ok(Response::new_err(
+            request.id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            format!("unhandled request: {}", request.method),
+        ))
+    }
+
+    /// Apply a document notification, returning the URI whose diagnostics need publishing
+    fn handle_notification(&mut self, notification: Notification) -> Result<Option<Uri>, String> {
+        let notification = match cast_notification::<DidOpenTextDocument>(notification) {
+            Ok(params) => {
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                return Ok(Some(document.uri));
+            }
+            Err(notification) => notification?,
+        };
+
+        let notification = match cast_notification::<DidChangeTextDocument>(notification) {
+            Ok(params) => {
+                // Full sync: the last change holds the whole document
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri.clone(), change.text);
+                }
+                return Ok(Some(params.text_document.uri));
+            }
+            Err(notification) => notification?,
+        };
+
+        if let Ok(params) = cast_notification::<DidCloseTextDocument>(notification) {
+            self.documents.remove(&params.text_document.uri);
+            return Ok(Some(params.text_document.uri));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Extract typed params, returning the untouched request if the method does not match
+fn cast_request<R: LspRequest>(
+    request: Request,
+) -> Result<(RequestId, R::Params), Result<Request, String>> {
+    request.extract(R::METHOD).map_err(|error| match error {
+        ExtractError::MethodMismatch(request) => Ok(request),
+        ExtractError::JsonError { method, error } => Err(format!("invalid params for {}: {}", method, error)),
+    })
+}
+
+fn cast_notification<N: LspNotification>(
+    notification: Notification,
+) -> Result<N::Params, Result<Notification, String>> {
+    notification.extract(N::METHOD).map_err(|error| match error {
+        ExtractError::MethodMismatch(notification) => Ok(notification),
+        ExtractError::JsonError { method, error } => Err(format!("invalid params for {}: {}", method, error)),
+    })
+}
+
+#[cfg(test)]
+mod tests;
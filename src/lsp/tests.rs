@@ -0,0 +1,66 @@
+use super::*;
+
+const CHART: &str = "=== <>Song\n= Intro\nAm %\n= Verse 1\n- **Loud**\nF#m7b5 C\n";
+
+#[test]
+fn test_line_index_utf16() {
+    let text = "= 2011 • *21*\nAm";
+    let index = LineIndex::new(text);
+    let offset = text.find("*21").unwrap();
+    assert_eq!(index.position(offset), Position::new(0, 9));
+    assert_eq!(index.offset(Position::new(0, 9)), offset);
+    assert_eq!(index.position(text.len()), Position::new(1, 2));
+}
+
+#[test]
+fn test_diagnostics_for_parse_error() {
+    assert!(diagnostics(CHART).is_empty());
+
+    let diagnostics = diagnostics("= Intro\n=== *Unclosed");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range.start.line, 1);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+}
+
+#[test]
+fn test_semantic_tokens() {
+    let tokens = semantic_tokens("= **Loud**\nAm");
+    let types: Vec<u32> = tokens.iter().map(|t| t.token_type).collect();
+    // level, emphasis open, bold text, emphasis close, chord
+    assert_eq!(types, [0, 1, 5, 1, 2]);
+    assert_eq!(tokens[2].token_modifiers_bitset, BOLD);
+    assert_eq!(tokens[4].delta_line, 1);
+    assert_eq!(tokens[4].delta_start, 0);
+    assert_eq!(tokens[4].length, 2);
+}
+
+#[test]
+fn test_document_symbols_nest_sections() {
+    let symbols = document_symbols(CHART);
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "Song");
+
+    let sections = symbols[0].children.as_ref().unwrap();
+    let names: Vec<&str> = sections.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Intro", "Verse 1"]);
+    assert_eq!(sections[0].range.end.line, 2);
+    assert_eq!(sections[1].range.end.line, 5);
+}
+
+#[test]
+fn test_hover_lists_chord_notes() {
+    let hover = hover(CHART, Position::new(5, 2)).unwrap();
+    let HoverContents::Markup(content) = hover.contents else {
+        panic!("expected markup hover");
+    };
+    assert_eq!(content.value, "**F#m7b5**\n\nF# A C E");
+    assert!(super::hover(CHART, Position::new(1, 3)).is_none());
+}
+
+#[test]
+fn test_malformed_request_gets_error_reply() {
+    let request = Request::new(RequestId::from(7), HoverRequest::METHOD.to_string(), serde_json::json!({}));
+    let response = Server::default().handle_request(request);
+    assert_eq!(response.id, RequestId::from(7));
+    assert_eq!(response.error.unwrap().code, lsp_server::ErrorCode::InvalidParams as i32);
+}
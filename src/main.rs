@@ -1,3 +1,8 @@
-fn main() {
-    println!("chord-script");
+mod cli;
+
+use clap::Parser;
+
+fn main() -> miette::Result<()> {
+    cli::Cli::parse().run()?;
+    Ok(())
 }
@@ -0,0 +1,749 @@
+// Chord symbols and chord line structure
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Natural note letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoteName {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl NoteName {
+    const ALL: [NoteName; 7] = [
+        NoteName::C,
+        NoteName::D,
+        NoteName::E,
+        NoteName::F,
+        NoteName::G,
+        NoteName::A,
+        NoteName::B,
+    ];
+
+    /// Pitch class of the natural note (C = 0)
+    pub fn pitch_class(self) -> i32 {
+        match self {
+            NoteName::C => 0,
+            NoteName::D => 2,
+            NoteName::E => 4,
+            NoteName::F => 5,
+            NoteName::G => 7,
+            NoteName::A => 9,
+            NoteName::B => 11,
+        }
+    }
+
+    /// The letter `steps` letters above this one, wrapping from B to C
+    pub fn step(self, steps: usize) -> NoteName {
+        let index = Self::ALL.iter().position(|&name| name == self).unwrap_or(0);
+        Self::ALL[(index + steps) % Self::ALL.len()]
+    }
+
+    pub fn from_char(c: char) -> Option<NoteName> {
+        match c {
+            'C' => Some(NoteName::C),
+            'D' => Some(NoteName::D),
+            'E' => Some(NoteName::E),
+            'F' => Some(NoteName::F),
+            'G' => Some(NoteName::G),
+            'A' => Some(NoteName::A),
+            'B' => Some(NoteName::B),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(self) -> char {
+        match self {
+            NoteName::C => 'C',
+            NoteName::D => 'D',
+            NoteName::E => 'E',
+            NoteName::F => 'F',
+            NoteName::G => 'G',
+            NoteName::A => 'A',
+            NoteName::B => 'B',
+        }
+    }
+}
+
+/// Accidental applied to a note letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accidental {
+    DoubleFlat,
+    Flat,
+    Natural,
+    Sharp,
+    DoubleSharp,
+}
+
+impl Accidental {
+    /// Semitone offset of the accidental
+    pub fn offset(self) -> i32 {
+        match self {
+            Accidental::DoubleFlat => -2,
+            Accidental::Flat => -1,
+            Accidental::Natural => 0,
+            Accidental::Sharp => 1,
+            Accidental::DoubleSharp => 2,
+        }
+    }
+
+    pub fn from_offset(offset: i32) -> Option<Accidental> {
+        match offset {
+            -2 => Some(Accidental::DoubleFlat),
+            -1 => Some(Accidental::Flat),
+            0 => Some(Accidental::Natural),
+            1 => Some(Accidental::Sharp),
+            2 => Some(Accidental::DoubleSharp),
+            _ => None,
+        }
+    }
+
+    /// ASCII spelling as written in chord-script sources
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Accidental::DoubleFlat => "bb",
+            Accidental::Flat => "b",
+            Accidental::Natural => "",
+            Accidental::Sharp => "#",
+            Accidental::DoubleSharp => "##",
+        }
+    }
+}
+
+/// A spelled note (letter plus accidental)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Note {
+    pub name: NoteName,
+    pub accidental: Accidental,
+}
+
+impl Note {
+    pub fn new(name: NoteName, accidental: Accidental) -> Self {
+        Self { name, accidental }
+    }
+
+    /// Pitch class in the range 0..12 (C = 0)
+    pub fn pitch_class(&self) -> i32 {
+        (self.name.pitch_class() + self.accidental.offset()).rem_euclid(12)
+    }
+
+    /// Spell the note `steps` letters and `semitones` semitones above this one.
+    ///
+    /// Falls back to a sharp or flat spelling when the interval would need
+    /// more than a double accidental.
+    pub fn interval(&self, steps: usize, semitones: i32) -> Note {
+        let name = self.name.step(steps);
+        let target = (self.pitch_class() + semitones).rem_euclid(12);
+        let offset = (target - name.pitch_class() + 6).rem_euclid(12) - 6;
+        match Accidental::from_offset(offset) {
+            Some(accidental) => Note::new(name, accidental),
+            None => Note::from_pitch_class(target, self.accidental == Accidental::Flat),
+        }
+    }
+
+    /// Spell a pitch class using sharps, or flats when `prefer_flats` is set
+    pub fn from_pitch_class(pitch_class: i32, prefer_flats: bool) -> Note {
+        const SHARPS: [(NoteName, Accidental); 12] = [
+            (NoteName::C, Accidental::Natural),
+            (NoteName::C, Accidental::Sharp),
+            (NoteName::D, Accidental::Natural),
+            (NoteName::D, Accidental::Sharp),
+            (NoteName::E, Accidental::Natural),
+            (NoteName::F, Accidental::Natural),
+            (NoteName::F, Accidental::Sharp),
+            (NoteName::G, Accidental::Natural),
+            (NoteName::G, Accidental::Sharp),
+            (NoteName::A, Accidental::Natural),
+            (NoteName::A, Accidental::Sharp),
+            (NoteName::B, Accidental::Natural),
+        ];
+        const FLATS: [(NoteName, Accidental); 12] = [
+            (NoteName::C, Accidental::Natural),
+            (NoteName::D, Accidental::Flat),
+            (NoteName::D, Accidental::Natural),
+            (NoteName::E, Accidental::Flat),
+            (NoteName::E, Accidental::Natural),
+            (NoteName::F, Accidental::Natural),
+            (NoteName::G, Accidental::Flat),
+            (NoteName::G, Accidental::Natural),
+            (NoteName::A, Accidental::Flat),
+            (NoteName::A, Accidental::Natural),
+            (NoteName::B, Accidental::Flat),
+            (NoteName::B, Accidental::Natural),
+        ];
+        let table = if prefer_flats { &FLATS } else { &SHARPS };
+        let (name, accidental) = table[pitch_class.rem_euclid(12) as usize];
+        Note::new(name, accidental)
+    }
+
+    /// Move the note by `semitones`, respelling it with sharps or flats
+    pub fn transpose(&self, semitones: i32, prefer_flats: bool) -> Note {
+        Note::from_pitch_class(self.pitch_class() + semitones, prefer_flats)
+    }
+
+    /// Whether a key with this tonic is conventionally written with flats
+    pub fn prefers_flats(&self) -> bool {
+        self.accidental.offset() < 0 || self.name == NoteName::F
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.name.as_char(), self.accidental.as_str())
+    }
+}
+
+/// A key: its tonic and mode, written `Bb` or `Gm`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub tonic: Note,
+    pub minor: bool,
+}
+
+impl Key {
+    pub fn new(tonic: Note, minor: bool) -> Self {
+        Self { tonic, minor }
+    }
+
+    /// Whether the key signature has flats; a minor key shares its relative major's
+    pub fn prefers_flats(&self) -> bool {
+        if !self.minor {
+            return self.tonic.prefers_flats();
+        }
+        self.tonic.accidental.offset() < 0
+            || matches!(self.tonic.name, NoteName::C | NoteName::D | NoteName::F | NoteName::G)
+    }
+
+    /// Move the tonic by `semitones`, keeping the mode
+    pub fn transpose(&self, semitones: i32, prefer_flats: bool) -> Key {
+        Key::new(self.tonic.transpose(semitones, prefer_flats), self.minor)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.tonic, if self.minor { "m" } else { "" })
+    }
+}
+
+/// Error returned when a string is not a valid chord symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordParseError(pub String);
+
+impl fmt::Display for ChordParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid chord symbol '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ChordParseError {}
+
+/// Parse a note at the start of `input`, returning it and the remaining text
+fn split_note(input: &str) -> Option<(Note, &str)> {
+    let mut chars = input.chars();
+    let name = NoteName::from_char(chars.next()?)?;
+    let rest = chars.as_str();
+    let (accidental, rest) = if let Some(rest) = rest.strip_prefix('#').or(rest.strip_prefix('♯')) {
+        (Accidental::Sharp, rest)
+    } else if let Some(rest) = rest.strip_prefix('b').or(rest.strip_prefix('♭')) {
+        (Accidental::Flat, rest)
+    } else {
+        (Accidental::Natural, rest)
+    };
+    Some((Note::new(name, accidental), rest))
+}
+
+impl FromStr for Note {
+    type Err = ChordParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_note(s) {
+            Some((note, "")) => Ok(note),
+            _ => Err(ChordParseError(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = ChordParseError;
+
+    /// A tonic, followed by `m`, `min` or `-` for minor keys
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_note(s) {
+            Some((tonic, "" | "maj")) => Ok(Key::new(tonic, false)),
+            Some((tonic, "m" | "min" | "-")) => Ok(Key::new(tonic, true)),
+            _ => Err(ChordParseError(s.to_string())),
+        }
+    }
+}
+
+/// A chord symbol such as `Am7`, `F#m7b5` or `C/E`
+///
+/// Serialized as its symbol, so charts stay readable as JSON or YAML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Chord {
+    pub root: Note,
+    /// Quality and extensions exactly as written (e.g. `m7b5`, `maj7`, `sus4`)
+    pub quality: String,
+    /// Slash bass note, if any
+    pub bass: Option<Note>,
+}
+
+impl Chord {
+    pub fn new(root: Note, quality: impl Into<String>, bass: Option<Note>) -> Self {
+        Self {
+            root,
+            quality: quality.into(),
+            bass,
+        }
+    }
+
+    /// Chord tones as (scale degree, semitones above the root), in ascending degree order.
+    ///
+    /// Quality parsing is best-effort: unrecognised characters are ignored.
+    pub fn intervals(&self) -> Vec<(usize, i32)> {
+        let mut tones: BTreeMap<usize, i32> = BTreeMap::from([(1, 0), (3, 4), (5, 7)]);
+        let mut major_seventh = false;
+        let mut diminished = false;
+        let mut rest = self.quality.as_str();
+
+        // Triad quality prefix
+        if let Some(r) = rest.strip_prefix("ø") {
+            tones.extend([(3, 3), (5, 6), (7, 10)]);
+            rest = r;
+        } else if let Some(r) = ["dim", "°", "o"].iter().find_map(|p| rest.strip_prefix(p)) {
+            tones.extend([(3, 3), (5, 6)]);
+            diminished = true;
+            rest = r;
+        } else if let Some(r) = ["aug", "+"].iter().find_map(|p| rest.strip_prefix(p)) {
+            tones.insert(5, 8);
+            rest = r;
+        } else if rest.starts_with("maj") {
+            // Handled below as a seventh quality
+        } else if let Some(r) = ["min", "m", "-"].iter().find_map(|p| rest.strip_prefix(p)) {
+            tones.insert(3, 3);
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('5') {
+            tones.remove(&3);
+            rest = r;
+        }
+
+        while !rest.is_empty() {
+            if let Some(r) = ["maj", "Maj", "M", "△", "^", "Δ"].iter().find_map(|p| rest.strip_prefix(p)) {
+                major_seventh = true;
+                if !r.starts_with(|c: char| c.is_ascii_digit()) {
+                    tones.insert(7, 11);
+                }
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("sus2") {
+                tones.remove(&3);
+                tones.insert(2, 2);
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("sus4").or(rest.strip_prefix("sus")) {
+                tones.remove(&3);
+                tones.insert(4, 5);
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("add").or(rest.strip_prefix('/')) {
+                let (degree, r) = split_number(r);
+                if let Some(degree) = degree {
+                    tones.insert(degree, extension_semitones(degree));
+                }
+                rest = r;
+            } else if let Some(r) = ["b", "-", "♭"].iter().find_map(|p| rest.strip_prefix(p)) {
+                let (degree, r) = split_number(r);
+                if let Some(degree) = degree {
+                    tones.insert(degree, extension_semitones(degree) - 1);
+                }
+                rest = r;
+            } else if let Some(r) = ["#", "+", "♯"].iter().find_map(|p| rest.strip_prefix(p)) {
+                let (degree, r) = split_number(r);
+                if let Some(degree) = degree {
+                    tones.insert(degree, extension_semitones(degree) + 1);
+                }
+                rest = r;
+            } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                let (degree, r) = split_number(rest);
+                match degree {
+                    Some(6) => {
+                        tones.insert(6, 9);
+                    }
+                    Some(degree) if degree >= 7 => {
+                        let seventh = if major_seventh {
+                            11
+                        } else if diminished {
+                            9
+                        } else {
+                            10
+                        };
+                        tones.entry(7).or_insert(seventh);
+                        for extension in [9, 11, 13] {
+                            if extension <= degree && !(extension == 11 && degree == 13) {
+                                tones.entry(extension).or_insert(extension_semitones(extension));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                rest = r;
+            } else {
+                let mut chars = rest.chars();
+                chars.next();
+                rest = chars.as_str();
+            }
+        }
+
+        tones.into_iter().collect()
+    }
+
+    /// The chord's notes, spelled from the root, with the slash bass (if any) first
+    pub fn notes(&self) -> Vec<Note> {
+        let mut notes: Vec<Note> = self
+            .intervals()
+            .into_iter()
+            .map(|(degree, semitones)| self.root.interval(degree - 1, semitones))
+            .collect();
+        if let Some(bass) = self.bass {
+            notes.retain(|note| note.pitch_class() != bass.pitch_class());
+            notes.insert(0, bass);
+        }
+        notes
+    }
+
+    /// Move the root and bass by `semitones`, keeping the quality as written
+    pub fn transpose(&self, semitones: i32, prefer_flats: bool) -> Chord {
+        Chord {
+            root: self.root.transpose(semitones, prefer_flats),
+            quality: self.quality.clone(),
+            bass: self.bass.map(|bass| bass.transpose(semitones, prefer_flats)),
+        }
+    }
+}
+
+/// Semitones above the root for an unaltered scale degree
+fn extension_semitones(degree: usize) -> i32 {
+    const MAJOR_SCALE: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+    let octave = ((degree - 1) / 7) as i32;
+    MAJOR_SCALE[(degree - 1) % 7] + 12 * octave
+}
+
+/// Highest scale degree read from a quality; larger numbers are skipped
+const MAX_DEGREE: usize = 15;
+
+fn split_number(input: &str) -> (Option<usize>, &str) {
+    let end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let number = input[..end].parse().ok().filter(|n| (1..=MAX_DEGREE).contains(n));
+    (number, &input[end..])
+}
+
+impl FromStr for Chord {
+    type Err = ChordParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ChordParseError(s.to_string());
+        let (root, rest) = split_note(s).ok_or_else(error)?;
+        // A slash introduces a bass note, except before an added degree as in `6/9`
+        let (quality, bass) = match rest.rsplit_once('/') {
+            Some((_, degree)) if degree.starts_with(|c: char| c.is_ascii_digit()) => (rest, None),
+            Some((quality, bass)) => (quality, Some(bass.parse::<Note>().map_err(|_| error())?)),
+            None => (rest, None),
+        };
+        Ok(Chord::new(root, quality, bass))
+    }
+}
+
+impl TryFrom<String> for Chord {
+    type Error = ChordParseError;
+
+    fn try_from(symbol: String) -> Result<Self, Self::Error> {
+        symbol.parse()
+    }
+}
+
+impl From<Chord> for String {
+    fn from(chord: Chord) -> Self {
+        chord.to_string()
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.quality)?;
+        if let Some(bass) = self.bass {
+            write!(f, "/{}", bass)?;
+        }
+        Ok(())
+    }
+}
+
+/// A chord struck within a bar, with its rhythmic modifiers
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChordHit {
+    pub chord: Chord,
+    /// Anticipated ahead of the beat (`<Chord`)
+    pub push: bool,
+    /// Accented stab (`<>Chord`)
+    pub accent: bool,
+    /// Ghost or optional chord (`Chord?`)
+    pub ghost: bool,
+}
+
+impl ChordHit {
+    pub fn plain(chord: Chord) -> Self {
+        Self {
+            chord,
+            push: false,
+            accent: false,
+            ghost: false,
+        }
+    }
+}
+
+/// A beat slot within a bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Beat {
+    /// A chord struck on this beat
+    Chord(ChordHit),
+    /// Explicit no chord (`N.C.`)
+    NoChord,
+    /// Empty beat (`,`): nothing new is struck
+    Empty,
+}
+
+/// A bar of beats. A bar with a single beat lasts the whole bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bar {
+    pub beats: Vec<Beat>,
+    /// Hold the last chord of the bar (`fermata`)
+    pub fermata: bool,
+}
+
+impl Bar {
+    pub fn new(beats: Vec<Beat>) -> Self {
+        Self {
+            beats,
+            fermata: false,
+        }
+    }
+
+    /// Chords struck in this bar, in order
+    pub fn chords(&self) -> impl Iterator<Item = &ChordHit> {
+        self.beats.iter().filter_map(|beat| match beat {
+            Beat::Chord(hit) => Some(hit),
+            _ => None,
+        })
+    }
+
+    /// Transpose every chord in the bar in place
+    pub fn transpose(&mut self, semitones: i32, prefer_flats: bool) {
+        for beat in &mut self.beats {
+            if let Beat::Chord(hit) = beat {
+                hit.chord = hit.chord.transpose(semitones, prefer_flats);
+            }
+        }
+    }
+}
+
+/// A repeated group of chord items (`( ... ) Nx`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepeatGroup {
+    pub items: Vec<ChordItem>,
+    /// Number of times the group is played, if given
+    pub times: Option<u32>,
+}
+
+/// An element of a chord line
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum ChordItem {
+    Bar(Bar),
+    /// Repeat the previous bar (`%`)
+    RepeatBar,
+    Group(RepeatGroup),
+    /// Start of a numbered ending (`1.`, `2.`)
+    Ending(u32),
+    /// Inline annotation (`"text"`)
+    Annotation(String),
+}
+
+impl ChordItem {
+    /// First chord struck in this item, looking inside repeat groups
+    pub fn first_chord(&self) -> Option<&Chord> {
+        match self {
+            ChordItem::Bar(bar) => bar.chords().next().map(|hit| &hit.chord),
+            ChordItem::Group(group) => group.items.iter().find_map(ChordItem::first_chord),
+            _ => None,
+        }
+    }
+
+    /// Transpose every chord in this item in place
+    pub fn transpose(&mut self, semitones: i32, prefer_flats: bool) {
+        match self {
+            ChordItem::Bar(bar) => bar.transpose(semitones, prefer_flats),
+            ChordItem::Group(group) => {
+                for item in &mut group.items {
+                    item.transpose(semitones, prefer_flats);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Beat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Beat::Chord(hit) => {
+                if hit.accent {
+                    write!(f, "<>")?;
+                }
+                if hit.push {
+                    write!(f, "<")?;
+                }
+                write!(f, "{}", hit.chord)?;
+                if hit.ghost {
+                    write!(f, "?")?;
+                }
+                Ok(())
+            }
+            Beat::NoChord => write!(f, "N.C."),
+            Beat::Empty => write!(f, ","),
+        }
+    }
+}
+
+impl fmt::Display for Bar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut previous: Option<&Beat> = None;
+        for beat in &self.beats {
+            let joins = !matches!(beat, Beat::Empty)
+                && previous.is_some_and(|previous| !matches!(previous, Beat::Empty));
+            if joins {
+                write!(f, "_")?;
+            }
+            write!(f, "{}", beat)?;
+            previous = Some(beat);
+        }
+        if self.fermata {
+            write!(f, " fermata")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ChordItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChordItem::Bar(bar) => write!(f, "{}", bar),
+            ChordItem::RepeatBar => write!(f, "%"),
+            ChordItem::Group(group) => {
+                write!(f, "(")?;
+                for (index, item) in group.items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")?;
+                if let Some(times) = group.times {
+                    write!(f, " {}x", times)?;
+                }
+                Ok(())
+            }
+            ChordItem::Ending(number) => write!(f, "{}.", number),
+            ChordItem::Annotation(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_names(chord: &str) -> Vec<String> {
+        chord
+            .parse::<Chord>()
+            .unwrap()
+            .notes()
+            .iter()
+            .map(|note| note.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_chord_symbol() {
+        let chord: Chord = "F#m7b5/C".parse().unwrap();
+        assert_eq!(chord.root, Note::new(NoteName::F, Accidental::Sharp));
+        assert_eq!(chord.quality, "m7b5");
+        assert_eq!(chord.bass, Some(Note::new(NoteName::C, Accidental::Natural)));
+        assert_eq!(chord.to_string(), "F#m7b5/C");
+        assert!("H7".parse::<Chord>().is_err());
+
+        let six_nine: Chord = "C6/9".parse().unwrap();
+        assert_eq!((six_nine.quality.as_str(), six_nine.bass), ("6/9", None));
+        let slash: Chord = "C/G".parse().unwrap();
+        assert_eq!((slash.quality.as_str(), slash.bass), ("", Some(Note::new(NoteName::G, Accidental::Natural))));
+        assert_eq!("C6/9/E".parse::<Chord>().unwrap().to_string(), "C6/9/E");
+    }
+
+    #[test]
+    fn test_chord_notes() {
+        assert_eq!(note_names("Am"), ["A", "C", "E"]);
+        assert_eq!(note_names("Cmaj7"), ["C", "E", "G", "B"]);
+        assert_eq!(note_names("Ebm7"), ["Eb", "Gb", "Bb", "Db"]);
+        assert_eq!(note_names("F#m7b5"), ["F#", "A", "C", "E"]);
+        assert_eq!(note_names("G7sus4"), ["G", "C", "D", "F"]);
+        assert_eq!(note_names("Bdim7"), ["B", "D", "F", "Ab"]);
+        assert_eq!(note_names("C9"), ["C", "E", "G", "Bb", "D"]);
+        assert_eq!(note_names("C/E"), ["E", "C", "G"]);
+        assert_eq!(note_names("C6/9"), ["C", "E", "G", "A", "D"]);
+        // Degrees past two octaves are ignored rather than overflowing
+        assert_eq!(note_names("Cadd9999999999999999999"), ["C", "E", "G"]);
+    }
+
+    #[test]
+    fn test_bar_display() {
+        let chord = |s: &str| Beat::Chord(ChordHit::plain(s.parse().unwrap()));
+        let bar = Bar::new(vec![chord("Em"), Beat::Empty, chord("G"), Beat::Empty]);
+        assert_eq!(bar.to_string(), "Em,G,");
+        let bar = Bar::new(vec![chord("Am"), chord("G")]);
+        assert_eq!(bar.to_string(), "Am_G");
+    }
+
+    #[test]
+    fn test_transpose_respells() {
+        let chord: Chord = "F#m7b5/C".parse().unwrap();
+        assert_eq!(chord.transpose(2, false).to_string(), "G#m7b5/D");
+        assert_eq!(chord.transpose(-1, true).to_string(), "Fm7b5/B");
+        assert!("Bb".parse::<Note>().unwrap().prefers_flats());
+        assert!(!"G".parse::<Note>().unwrap().prefers_flats());
+    }
+
+    #[test]
+    fn test_key_mode() {
+        let key: Key = "Gm".parse().unwrap();
+        assert!(key.minor);
+        assert!(key.prefers_flats());
+        assert!(!"G".parse::<Key>().unwrap().prefers_flats());
+        assert!(!"Em".parse::<Key>().unwrap().prefers_flats());
+        assert_eq!(key.transpose(2, false).to_string(), "Am");
+        assert!("Gsus".parse::<Key>().is_err());
+    }
+}
@@ -0,0 +1,136 @@
+// Versioned JSON and YAML documents for charts
+//
+// A document is the chart's fields plus a top-level `version`, described by
+// `docs/chart.schema.json`. The version is bumped whenever the shape of the model changes
+// incompatibly, and documents from other versions are rejected rather than misread.
+
+use super::Chart;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Version of the document schema written by this build
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Error reading or writing a chart document
+#[derive(Debug, Error, Diagnostic)]
+pub enum DocumentError {
+    /// Malformed JSON, or a document (JSON or YAML) that does not match the schema
+    #[error("invalid chart document: {0}")]
+    #[diagnostic(code(model::document::json))]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid YAML: {0}")]
+    #[diagnostic(code(model::document::yaml))]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("unsupported chart document version {found} (this build reads version {SCHEMA_VERSION})")]
+    #[diagnostic(code(model::document::version))]
+    Version { found: u32 },
+}
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    #[serde(flatten)]
+    chart: &'a Chart,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    #[serde(flatten)]
+    chart: Chart,
+}
+
+/// Just the version, read before the rest so newer documents fail with a clear error
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+impl Chart {
+    /// Serialize as a pretty-printed JSON document
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(&self.document())?)
+    }
+
+    /// Serialize as a YAML document with the same structure as the JSON one
+    pub fn to_yaml(&self) -> Result<String, DocumentError> {
+        // Going through JSON values writes enums as plain maps instead of YAML tags,
+        // which other YAML readers would reject
+        let value = serde_json::to_value(self.document())?;
+        Ok(serde_yaml::to_string(&value)?)
+    }
+
+    /// Read a JSON document written by [`Chart::to_json`]
+    pub fn from_json(input: &str) -> Result<Self, DocumentError> {
+        check_version(serde_json::from_str::<Header>(input)?.version)?;
+        Ok(serde_json::from_str::<Document>(input)?.chart)
+    }
+
+    /// Read a YAML document written by [`Chart::to_yaml`]
+    pub fn from_yaml(input: &str) -> Result<Self, DocumentError> {
+        let value: serde_json::Value = serde_yaml::from_str(input)?;
+        check_version(Header::deserialize(&value)?.version)?;
+        Ok(Document::deserialize(value)?.chart)
+    }
+
+    fn document(&self) -> DocumentRef<'_> {
+        DocumentRef {
+            version: SCHEMA_VERSION,
+            chart: self,
+        }
+    }
+}
+
+fn check_version(found: u32) -> Result<(), DocumentError> {
+    match found {
+        SCHEMA_VERSION => Ok(()),
+        found => Err(DocumentError::Version { found }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    const CHART: &str = "=== <>Blue Bossa\n- <Latin *feel*\n<>Cm7_, % (Fm7 1. Bb7? 2. N.C.) 2x \"fine\"\n";
+
+    #[test]
+    fn test_json_shape() {
+        let chart = parse_chart("- <*Swing*\nAm7_D7\n").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&chart.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["lines"][0]["level"], "text");
+        assert_eq!(json["lines"][0]["left"][0], serde_json::json!({ "text": "Swing", "style": "italic" }));
+        assert_eq!(
+            json["lines"][1]["chords"][0]["bar"]["beats"][1],
+            serde_json::json!({ "chord": { "chord": "D7", "push": false, "accent": false, "ghost": false } })
+        );
+    }
+
+    #[test]
+    fn test_schema_matches_version() {
+        let schema: serde_json::Value = serde_json::from_str(include_str!("../../docs/chart.schema.json")).unwrap();
+        assert_eq!(schema["properties"]["version"]["const"], SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let chart = parse_chart(CHART).unwrap();
+        assert_eq!(Chart::from_json(&chart.to_json().unwrap()).unwrap(), chart);
+        assert_eq!(Chart::from_yaml(&chart.to_yaml().unwrap()).unwrap(), chart);
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let error = Chart::from_json("{\"version\": 2, \"lines\": [{\"new\": true}]}").unwrap_err();
+        assert!(matches!(error, DocumentError::Version { found: 2 }));
+
+        let error = Chart::from_yaml("version: 1\nlines:\n- level: title\n").unwrap_err();
+        assert!(matches!(error, DocumentError::Json(_)));
+        assert!(matches!(Chart::from_yaml("version: [").unwrap_err(), DocumentError::Yaml(_)));
+    }
+}
@@ -0,0 +1,439 @@
+// Musical form: chord lines resolved into measures, and the key and meter read from text lines
+
+use super::{Bar, Chart, ChordItem, Key, LineLevel, TextSpan};
+use std::fmt;
+
+/// Most beats a time signature may have
+const MAX_BEATS: u32 = 32;
+
+/// Labels of `=` lines that describe the song rather than name a section, such as `Key: Gm`
+const METADATA_LABELS: [&str; 4] = ["key:", "tempo:", "time:", "style:"];
+
+/// A time signature such as 4/4 or 6/8
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSignature {
+    pub beats: u32,
+    pub beat_type: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            beats: 4,
+            beat_type: 4,
+        }
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.beat_type)
+    }
+}
+
+/// A bar in reading order, with the repeat structure around it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measure {
+    /// The bar's content, with `%` resolved to the bar it repeats
+    pub bar: Bar,
+    /// Section header (`=` line) that starts at this measure
+    pub section: Option<String>,
+    /// Inline annotations placed before this measure
+    pub annotations: Vec<String>,
+    /// A repeated section starts here
+    pub repeat_start: bool,
+    /// A repeated section ends here and is played this many times in total
+    pub repeat_end: Option<u32>,
+    /// Numbered ending this measure belongs to
+    pub ending: Option<Volta>,
+}
+
+impl Measure {
+    fn new(bar: Bar) -> Self {
+        Self {
+            bar,
+            section: None,
+            annotations: Vec::new(),
+            repeat_start: false,
+            repeat_end: None,
+            ending: None,
+        }
+    }
+}
+
+/// Membership of a measure in a numbered ending (volta bracket)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Volta {
+    pub number: u32,
+    /// First measure of the ending
+    pub start: bool,
+    /// Last measure of the ending
+    pub stop: bool,
+}
+
+impl Chart {
+    /// Time signature from a text line such as `= >4/4` or `Time: 6/8`, if any. Bars of more
+    /// than `MAX_BEATS` beats are not read as meters.
+    pub fn time_signature(&self) -> Option<TimeSignature> {
+        self.words().find_map(|word| meter(&word))
+    }
+
+    /// Tempo in beats per minute from a text line such as `Tempo: 120` or `96 bpm`, if any
+    pub fn tempo(&self) -> Option<u32> {
+        let words: Vec<String> = self.words().collect();
+        words.windows(2).find_map(|pair| {
+            let (first, second) = (pair[0].to_lowercase(), pair[1].to_lowercase());
+            if first.trim_end_matches(':') == "tempo" || first.ends_with('=') {
+                second.parse().ok()
+            } else if second == "bpm" {
+                first.parse().ok()
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Key from a text line such as `= >Key: Gm` or `- Key: Bb major`, if any
+    pub fn key(&self) -> Option<Key> {
+        let words: Vec<String> = self.words().collect();
+        words.windows(2).enumerate().find_map(|(index, pair)| {
+            if !pair[0].eq_ignore_ascii_case("key:") {
+                return None;
+            }
+            let key: Key = pair[1].parse().ok()?;
+            match words.get(index + 2).map(|word| word.to_lowercase()).as_deref() {
+                Some("minor") => Some(Key::new(key.tonic, true)),
+                Some("major") => Some(Key::new(key.tonic, false)),
+                _ => Some(key),
+            }
+        })
+    }
+
+    /// The stated key, or else the first chord's root, minor when the chord has a minor third
+    pub fn key_or_first_chord(&self) -> Option<Key> {
+        self.key().or_else(|| {
+            self.playback().iter().find_map(|bar| bar.chords().next()).map(|hit| {
+                Key::new(hit.chord.root, hit.chord.intervals().contains(&(3, 3)))
+            })
+        })
+    }
+
+    /// Respell the key after each `Key:` label in text lines
+    pub(super) fn transpose_key(&mut self, semitones: i32, prefer_flats: bool) {
+        let text_lines = self.lines.iter_mut().filter(|line| line.level != LineLevel::Chords);
+        for spans in text_lines.flat_map(|line| [&mut line.left, &mut line.center, &mut line.right]) {
+            let mut after_label = false;
+            for span in spans {
+                let mut text = String::new();
+                for piece in span.text.split_inclusive(char::is_whitespace) {
+                    let word = piece.trim_end();
+                    match word.parse::<Key>() {
+                        Ok(key) if after_label => {
+                            text.push_str(&key.transpose(semitones, prefer_flats).to_string());
+                            text.push_str(&piece[word.len()..]);
+                        }
+                        _ => text.push_str(piece),
+                    }
+                    if !word.is_empty() {
+                        after_label = word.eq_ignore_ascii_case("key:");
+                    }
+                }
+                span.text = text;
+            }
+        }
+    }
+
+    /// Words of all text lines, in order
+    fn words(&self) -> impl Iterator<Item = String> + '_ {
+        self.lines
+            .iter()
+            .filter(|line| line.level != LineLevel::Chords)
+            .flat_map(|line| [&line.left, &line.center, &line.right])
+            .flat_map(|spans| {
+                TextSpan::join(spans)
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+    }
+
+    /// All chord lines as measures in reading order, repeats written once
+    pub fn measures(&self) -> Vec<Measure> {
+        self.line_measures().into_iter().flatten().collect()
+    }
+
+    /// Measures of each line, in line order, as `measures` splits them; text lines have none
+    pub fn line_measures(&self) -> Vec<Vec<Measure>> {
+        let mut builder = MeasureBuilder::default();
+        let mut ends = Vec::new();
+        for line in &self.lines {
+            match line.level {
+                LineLevel::Chords => builder.items(&line.chords),
+                LineLevel::Header3 => {
+                    let columns: Vec<String> = [&line.left, &line.center, &line.right]
+                        .into_iter()
+                        .map(|column| TextSpan::join(column).trim().to_string())
+                        .filter(|text| !text.is_empty())
+                        .collect();
+                    // Metadata such as `Key: Gm` is not a section name; lines holding only
+                    // metadata leave the current section as it is
+                    let names: Vec<String> = columns.iter().filter(|text| !is_metadata(text)).cloned().collect();
+                    if columns.is_empty() || !names.is_empty() {
+                        builder.section = Some(names.join(" ")).filter(|text| !text.is_empty());
+                    }
+                }
+                _ => {}
+            }
+            ends.push(builder.measures.len());
+        }
+
+        let mut measures = builder.finish().into_iter();
+        let mut start = 0;
+        ends.into_iter()
+            .map(|end| {
+                let line = measures.by_ref().take(end - start).collect();
+                start = end;
+                line
+            })
+            .collect()
+    }
+
+    /// Bars in the order they are played: repeats unrolled and endings taken in turn
+    pub fn playback(&self) -> Vec<Bar> {
+        let mut bars = Vec::new();
+        for line in &self.lines {
+            unroll(&line.chords, &mut bars);
+        }
+        bars
+    }
+}
+
+/// Time signature written as a single word such as `3/4`
+fn meter(word: &str) -> Option<TimeSignature> {
+    let (beats, beat_type) = word.split_once('/')?;
+    let beats: u32 = beats.parse().ok()?;
+    let beat_type: u32 = beat_type.parse().ok()?;
+    ((1..=MAX_BEATS).contains(&beats) && matches!(beat_type, 1 | 2 | 4 | 8 | 16))
+        .then_some(TimeSignature { beats, beat_type })
+}
+
+/// Header text that describes the song (`Key: Gm`, `Tempo: 120`, `3/4`) rather than names a section
+fn is_metadata(text: &str) -> bool {
+    let first = text.split_whitespace().next().unwrap_or_default().to_lowercase();
+    METADATA_LABELS.contains(&first.as_str()) || meter(text).is_some()
+}
+
+#[derive(Default)]
+struct MeasureBuilder {
+    measures: Vec<Measure>,
+    section: Option<String>,
+    annotations: Vec<String>,
+}
+
+impl MeasureBuilder {
+    fn items(&mut self, items: &[ChordItem]) {
+        let (common, endings) = split_endings(items);
+        self.sequence(common);
+        for (number, items) in endings {
+            let first = self.measures.len();
+            self.sequence(items);
+            self.mark_ending(first, number);
+        }
+    }
+
+    fn sequence(&mut self, items: &[ChordItem]) {
+        for item in items {
+            match item {
+                ChordItem::Bar(bar) => self.push(bar.clone()),
+                ChordItem::RepeatBar => {
+                    let previous = self.measures.last().map(|measure| measure.bar.clone());
+                    self.push(previous.unwrap_or_else(|| Bar::new(Vec::new())));
+                }
+                ChordItem::Annotation(text) => self.annotations.push(text.clone()),
+                ChordItem::Group(group) => {
+                    let (common, endings) = split_endings(&group.items);
+                    let first = self.measures.len();
+                    self.sequence(common);
+                    if let Some(measure) = self.measures.get_mut(first) {
+                        measure.repeat_start = true;
+                    }
+
+                    let times = group.times.unwrap_or(endings.len().max(2) as u32);
+                    let last_ending = endings.len();
+                    for (index, (number, items)) in endings.into_iter().enumerate() {
+                        let start = self.measures.len();
+                        self.sequence(items);
+                        self.mark_ending(start, number);
+                        if index + 1 < last_ending {
+                            self.mark_repeat_end(times);
+                        }
+                    }
+                    if last_ending == 0 {
+                        self.mark_repeat_end(times);
+                    }
+                }
+                // Endings are split off before a sequence is laid out
+                ChordItem::Ending(_) => {}
+            }
+        }
+    }
+
+    fn push(&mut self, bar: Bar) {
+        let mut measure = Measure::new(bar);
+        measure.section = self.section.take();
+        measure.annotations = std::mem::take(&mut self.annotations);
+        self.measures.push(measure);
+    }
+
+    fn mark_ending(&mut self, start: usize, number: u32) {
+        let end = self.measures.len();
+        for (index, measure) in self.measures[start..].iter_mut().enumerate() {
+            measure.ending = Some(Volta {
+                number,
+                start: index == 0,
+                stop: start + index + 1 == end,
+            });
+        }
+    }
+
+    fn mark_repeat_end(&mut self, times: u32) {
+        if let Some(measure) = self.measures.last_mut() {
+            measure.repeat_end = Some(times);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Measure> {
+        // Trailing annotations belong to the last measure
+        if let Some(measure) = self.measures.last_mut() {
+            measure.annotations.append(&mut self.annotations);
+        }
+        self.measures
+    }
+}
+
+/// Split items at numbered endings: the common part, then each ending's number and items
+fn split_endings(items: &[ChordItem]) -> (&[ChordItem], Vec<(u32, &[ChordItem])>) {
+    let positions: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| matches!(item, ChordItem::Ending(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    let Some(&first) = positions.first() else {
+        return (items, Vec::new());
+    };
+
+    let endings = positions
+        .iter()
+        .enumerate()
+        .map(|(index, &position)| {
+            let end = positions.get(index + 1).copied().unwrap_or(items.len());
+            let ChordItem::Ending(number) = items[position] else {
+                unreachable!("positions only holds endings");
+            };
+            (number, &items[position + 1..end])
+        })
+        .collect();
+
+    (&items[..first], endings)
+}
+
+/// Append the bars of `items` in playing order
+fn unroll(items: &[ChordItem], bars: &mut Vec<Bar>) {
+    let (common, endings) = split_endings(items);
+    unroll_sequence(common, bars);
+    for (_, items) in endings {
+        unroll_sequence(items, bars);
+    }
+}
+
+fn unroll_sequence(items: &[ChordItem], bars: &mut Vec<Bar>) {
+    for item in items {
+        match item {
+            ChordItem::Bar(bar) => bars.push(bar.clone()),
+            ChordItem::RepeatBar => {
+                let previous = bars.last().cloned().unwrap_or_else(|| Bar::new(Vec::new()));
+                bars.push(previous);
+            }
+            ChordItem::Group(group) => {
+                let (common, endings) = split_endings(&group.items);
+                let passes = group.times.unwrap_or(endings.len().max(2) as u32) as usize;
+                for pass in 0..passes {
+                    unroll_sequence(common, bars);
+                    // Later passes reuse the last ending when there are more passes than endings
+                    if let Some((_, items)) = endings.get(pass.min(endings.len().saturating_sub(1))) {
+                        unroll_sequence(items, bars);
+                    }
+                }
+            }
+            ChordItem::Ending(_) | ChordItem::Annotation(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    fn names(bars: &[Bar]) -> Vec<String> {
+        bars.iter().map(|bar| bar.to_string()).collect()
+    }
+
+    #[test]
+    fn test_meter_from_text_lines() {
+        let chart = parse_chart("=== <>Waltz >3/4\n- >Tempo: 96\nC\n").unwrap();
+        assert_eq!(chart.time_signature(), Some(TimeSignature { beats: 3, beat_type: 4 }));
+        assert_eq!(chart.tempo(), Some(96));
+
+        let chart = parse_chart("- 140 bpm - 9000000/4\nC\n").unwrap();
+        assert_eq!(chart.tempo(), Some(140));
+        assert_eq!(chart.time_signature(), None);
+    }
+
+    #[test]
+    fn test_key_from_text_lines() {
+        let mut chart = parse_chart("= >Key: G minor\nAm7b5 D7 Gm\n").unwrap();
+        assert_eq!(chart.key().map(|key| key.to_string()).as_deref(), Some("Gm"));
+
+        chart.transpose(-2, true);
+        assert_eq!(chart.key().map(|key| key.to_string()).as_deref(), Some("Fm"));
+        assert_eq!(TextSpan::join(&chart.lines[0].right), "Key: F minor");
+        assert_eq!(parse_chart("Dm7 G7 C\n").unwrap().key(), None);
+        assert_eq!(parse_chart("Dm7 G7 C\n").unwrap().key_or_first_chord().map(|key| key.to_string()).as_deref(), Some("Dm"));
+    }
+
+    #[test]
+    fn test_measures_with_endings() {
+        let chart = parse_chart("= A\n(F G \"to coda\" Em 1. F 2. E) %\n").unwrap();
+        let measures = chart.measures();
+
+        let bars: Vec<Bar> = measures.iter().map(|measure| measure.bar.clone()).collect();
+        assert_eq!(names(&bars), ["F", "G", "Em", "F", "E", "E"]);
+        assert_eq!(measures[0].section.as_deref(), Some("A"));
+        assert!(measures[0].repeat_start);
+        assert_eq!(measures[2].annotations, ["to coda"]);
+        assert_eq!(measures[3].repeat_end, Some(2));
+        assert_eq!(measures[3].ending, Some(Volta { number: 1, start: true, stop: true }));
+        assert_eq!(measures[4].ending.map(|volta| volta.number), Some(2));
+        assert_eq!(measures[4].repeat_end, None);
+    }
+
+    #[test]
+    fn test_metadata_lines_are_not_sections() {
+        let chart = parse_chart("=== <>Song\n= >Key: Gm\nAm7b5 D7 Gm\n= <A >3/4\n= >Tempo: 96\nC\n").unwrap();
+        let measures = chart.measures();
+        assert_eq!(measures[0].section, None);
+        assert_eq!(measures[3].section.as_deref(), Some("A"));
+    }
+
+    #[test]
+    fn test_playback_unrolls_repeats() {
+        let chart = parse_chart("(Am G) 3x\n(F G 1. C 2. Dm)\n").unwrap();
+        assert_eq!(
+            names(&chart.playback()),
+            ["Am", "G", "Am", "G", "Am", "G", "F", "G", "C", "F", "G", "Dm"]
+        );
+    }
+}
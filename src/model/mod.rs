@@ -1,7 +1,23 @@
 // Model module for chord-script domain types
 
+mod chord;
+#[cfg(feature = "serde")]
+mod document;
+mod form;
+mod setlist;
+
+pub use chord::{
+    Accidental, Bar, Beat, Chord, ChordHit, ChordItem, ChordParseError, Key, Note, NoteName,
+    RepeatGroup,
+};
+#[cfg(feature = "serde")]
+pub use document::{DocumentError, SCHEMA_VERSION};
+pub use form::{Measure, TimeSignature, Volta};
+pub use setlist::{Setlist, SetlistEntry};
+
 /// Represents a complete music chart
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Chart {
     /// The lines that make up the chart content
     pub lines: Vec<Line>,
@@ -12,10 +28,63 @@ impl Chart {
     pub fn new(lines: Vec<Line>) -> Self {
         Self { lines }
     }
+
+    /// Title of the chart: the text of the first level 1 header, if any
+    pub fn title(&self) -> Option<String> {
+        self.header_text(LineLevel::Header1)
+    }
+
+    /// Subtitle of the chart (usually the artist or composer): the first level 2 header, if any
+    pub fn subtitle(&self) -> Option<String> {
+        self.header_text(LineLevel::Header2)
+    }
+
+    fn header_text(&self, level: LineLevel) -> Option<String> {
+        self.lines
+            .iter()
+            .find(|line| line.level == level)
+            .map(|line| {
+                [&line.left, &line.center, &line.right]
+                    .into_iter()
+                    .map(|column| TextSpan::join(column))
+                    .filter(|text| !text.trim().is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim()
+                    .to_string()
+            })
+            .filter(|text| !text.is_empty())
+    }
+
+    /// Transpose every chord line in place, along with the key named on a `Key:` line
+    pub fn transpose(&mut self, semitones: i32, prefer_flats: bool) {
+        for item in self.lines.iter_mut().flat_map(|line| &mut line.chords) {
+            item.transpose(semitones, prefer_flats);
+        }
+        self.transpose_key(semitones, prefer_flats);
+    }
+}
+
+/// A chart laid out on a single page, with an optional footer line at the bottom margin
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub chart: Chart,
+    pub footer: Option<Line>,
+}
+
+impl Page {
+    /// Create a page without a footer
+    pub fn new(chart: Chart) -> Self {
+        Self {
+            chart,
+            footer: None,
+        }
+    }
 }
 
 /// Text styling options for span of text
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum TextStyle {
     /// Normal, unstyled text
     Normal,
@@ -29,6 +98,7 @@ pub enum TextStyle {
 
 /// A styled span of text
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextSpan {
     pub text: String,
     pub style: TextStyle,
@@ -48,10 +118,21 @@ impl TextSpan {
             style: TextStyle::Normal,
         }
     }
+
+    /// Unstyled text of a column; spans are stored trimmed, so words are rejoined with spaces
+    pub fn join(spans: &[TextSpan]) -> String {
+        spans
+            .iter()
+            .map(|span| span.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
 /// Line level in the hierarchy
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum LineLevel {
     /// Header level 1 (major section)
     Header1,
@@ -61,15 +142,23 @@ pub enum LineLevel {
     Header3,
     /// Text line (stage directions, comments)
     Text,
+    /// Chord line (bars of chords)
+    Chords,
 }
 
 /// A line in a chart with three-column layout (left, center, right aligned)
+///
+/// Chord lines use `LineLevel::Chords`, keep their content in `chords` and leave the columns empty.
+/// Build lines with `Line::new`, `Line::chords` or `Line::plain_text`; fields may be added.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct Line {
     pub level: LineLevel,
     pub left: Vec<TextSpan>,
     pub center: Vec<TextSpan>,
     pub right: Vec<TextSpan>,
+    pub chords: Vec<ChordItem>,
 }
 impl Line {
     /// Create a new line with explicit columns and level
@@ -84,6 +173,18 @@ impl Line {
             left,
             center,
             right,
+            chords: Vec::new(),
+        }
+    }
+
+    /// Create a chord line from its items
+    pub fn chords(chords: Vec<ChordItem>) -> Self {
+        Self {
+            level: LineLevel::Chords,
+            left: Vec::new(),
+            center: Vec::new(),
+            right: Vec::new(),
+            chords,
         }
     }
 
@@ -99,6 +200,7 @@ impl Line {
             left: vec![TextSpan::plain(left)],
             center: vec![TextSpan::plain(center)],
             right: vec![TextSpan::plain(right)],
+            chords: Vec::new(),
         }
     }
-}
\ No newline at end of file
+}
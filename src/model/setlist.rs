@@ -0,0 +1,220 @@
+// Setlists: an ordered collection of charts compiled into one paginated document
+
+use super::{Chart, ChordItem, Key, Line, LineLevel, Page, TextSpan, TextStyle};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// An ordered list of charts to perform, with an optional title
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Setlist {
+    pub title: Option<String>,
+    pub entries: Vec<SetlistEntry>,
+}
+
+/// A single song in a setlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetlistEntry {
+    /// Path to the chart, relative to the manifest
+    pub path: PathBuf,
+    /// Key to transpose the chart to, if different from how it is written
+    pub key: Option<Key>,
+    /// Performance notes shown in the table of contents and page footer
+    pub notes: Option<String>,
+}
+
+impl SetlistEntry {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            key: None,
+            notes: None,
+        }
+    }
+}
+
+impl Setlist {
+    /// Lay out the setlist as pages: a table of contents followed by one page per chart.
+    ///
+    /// `charts` holds the parsed chart for each entry, in order. Key overrides are
+    /// applied here, moving from the chart's `Key:` line or else its first chord, and every
+    /// page gets a footer with the setlist title and page number. Each page is numbered as
+    /// one sheet; use `paginate` when a renderer can spill a page onto several.
+    pub fn pages(&self, charts: Vec<Chart>) -> Vec<Page> {
+        self.paginate(charts, |_| 1)
+    }
+
+    /// Lay out the setlist like `pages`, numbering the sheets each page fills as counted by
+    /// `sheets`, so the contents and footers match the printed document
+    pub fn paginate(&self, charts: Vec<Chart>, sheets: impl Fn(&Page) -> usize) -> Vec<Page> {
+        let charts: Vec<Chart> = self
+            .entries
+            .iter()
+            .zip(charts)
+            .map(|(entry, mut chart)| {
+                let written = chart.key().map(|key| key.tonic).or_else(|| {
+                    let mut chords = chart.lines.iter().flat_map(|line| &line.chords);
+                    chords.find_map(ChordItem::first_chord).map(|chord| chord.root)
+                });
+                if let (Some(key), Some(written)) = (entry.key, written) {
+                    // Move by the smallest interval: 5 semitones down to 6 up, so a tritone goes up
+                    let semitones = (key.tonic.pitch_class() - written.pitch_class() + 5).rem_euclid(12) - 5;
+                    chart.transpose(semitones, key.prefers_flats());
+                }
+                chart
+            })
+            .collect();
+
+        // Page numbers don't change how much room a page takes, so count with placeholders
+        let placeholder = |chart: Chart| Page {
+            chart,
+            footer: Some(self.footer(1..=1, 1, None)),
+        };
+        let contents_sheets = sheets(&placeholder(self.contents(&charts, &vec![1; charts.len()]))).max(1);
+        let mut first = contents_sheets + 1;
+        let mut ranges = Vec::new();
+        for chart in &charts {
+            let last = first + sheets(&placeholder(chart.clone())).max(1) - 1;
+            ranges.push(first..=last);
+            first = last + 1;
+        }
+        let total = first - 1;
+
+        let starts: Vec<usize> = ranges.iter().map(|range| *range.start()).collect();
+        let mut pages = vec![Page {
+            footer: Some(self.footer(1..=contents_sheets, total, None)),
+            chart: self.contents(&charts, &starts),
+        }];
+        for ((entry, chart), range) in self.entries.iter().zip(charts).zip(ranges) {
+            pages.push(Page {
+                chart,
+                footer: Some(self.footer(range, total, entry.notes.as_deref())),
+            });
+        }
+        pages
+    }
+
+    /// Table of contents: one entry per song with the page it starts on, its key and notes
+    fn contents(&self, charts: &[Chart], starts: &[usize]) -> Chart {
+        let title = self.title.as_deref().unwrap_or("Setlist");
+        let mut lines = vec![Line::new(
+            LineLevel::Header1,
+            vec![],
+            vec![TextSpan::plain(title)],
+            vec![],
+        )];
+
+        for (index, ((entry, chart), start)) in self.entries.iter().zip(charts).zip(starts).enumerate() {
+            lines.push(Line::new(
+                LineLevel::Header3,
+                vec![TextSpan::plain(format!("{}. {}", index + 1, entry.title(chart)))],
+                vec![],
+                vec![TextSpan::plain(start.to_string())],
+            ));
+
+            let details: Vec<String> = chart
+                .key()
+                .or(entry.key)
+                .map(|key| format!("Key: {}", key))
+                .into_iter()
+                .chain(entry.notes.clone())
+                .collect();
+            if !details.is_empty() {
+                lines.push(Line::new(
+                    LineLevel::Text,
+                    vec![TextSpan::new(details.join(" · "), TextStyle::Italic)],
+                    vec![],
+                    vec![],
+                ));
+            }
+        }
+
+        Chart::new(lines)
+    }
+
+    /// Footer line: setlist title on the left, notes in the center, page numbers on the right
+    fn footer(&self, numbers: RangeInclusive<usize>, total: usize, notes: Option<&str>) -> Line {
+        let numbers = match numbers.into_inner() {
+            (first, last) if first == last => first.to_string(),
+            (first, last) => format!("{}–{}", first, last),
+        };
+        Line::new(
+            LineLevel::Text,
+            self.title.iter().map(TextSpan::plain).collect(),
+            notes.map(|notes| TextSpan::new(notes, TextStyle::Italic)).into_iter().collect(),
+            vec![TextSpan::plain(format!("{} / {}", numbers, total))],
+        )
+    }
+}
+
+impl SetlistEntry {
+    /// Song title: the chart's own title, falling back to the file name
+    fn title(&self, chart: &Chart) -> String {
+        chart.title().unwrap_or_else(|| {
+            self.path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    #[test]
+    fn test_pages_transpose_and_number() {
+        let setlist = Setlist {
+            title: Some("Friday".to_string()),
+            entries: vec![
+                SetlistEntry {
+                    key: Some("Bbm".parse().unwrap()),
+                    notes: Some("capo 1".to_string()),
+                    ..SetlistEntry::new("songs/first.chords")
+                },
+                SetlistEntry::new("songs/second.chords"),
+            ],
+        };
+        let charts = vec![
+            parse_chart("=== <>Blue Bossa
+- >Key: Cm
+Dm7b5 G7 Cm
+").unwrap(),
+            parse_chart("Em
+").unwrap(),
+        ];
+
+        let pages = setlist.pages(charts);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[1].chart.key().unwrap().to_string(), "Bbm");
+        assert_eq!(pages[1].chart.lines[2].chords[1].to_string(), "F7");
+
+        let contents = &pages[0].chart.lines;
+        assert_eq!(contents[1].left[0].text, "1. Blue Bossa");
+        assert_eq!(contents[1].right[0].text, "2");
+        assert_eq!(contents[2].left[0].text, "Key: Bbm · capo 1");
+        assert_eq!(contents[3].left[0].text, "2. second");
+
+        let footer = pages[2].footer.as_ref().unwrap();
+        assert_eq!(footer.left[0].text, "Friday");
+        assert_eq!(footer.right[0].text, "3 / 3");
+    }
+
+    #[test]
+    fn test_paginate_numbers_every_sheet() {
+        let setlist = Setlist {
+            title: None,
+            entries: vec![SetlistEntry::new("long.chords"), SetlistEntry::new("short.chords")],
+        };
+        let charts = vec![parse_chart("=== <>Long\nC\n").unwrap(), parse_chart("G\n").unwrap()];
+
+        let pages = setlist.paginate(charts, |page| if page.chart.title().as_deref() == Some("Long") { 2 } else { 1 });
+        let numbers: Vec<&str> = pages
+            .iter()
+            .map(|page| page.footer.as_ref().unwrap().right[0].text.as_str())
+            .collect();
+        assert_eq!(numbers, ["1 / 4", "2–3 / 4", "4 / 4"]);
+        assert_eq!(pages[0].chart.lines[2].right[0].text, "4");
+    }
+}
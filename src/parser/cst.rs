@@ -0,0 +1,441 @@
+// Lossless concrete syntax tree for chord-script sources
+
+use crate::model::{
+    Bar, Beat, Chart, Chord, ChordHit, ChordItem, Line, LineLevel, RepeatGroup, TextSpan,
+    TextStyle,
+};
+use std::fmt;
+use std::ops::Range;
+
+/// Kind of a token or node in the concrete syntax tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // Tokens
+    /// Spaces, tabs and newlines between or inside lines
+    Whitespace,
+    /// Line level prefix (`===`, `==`, `=`, `-`)
+    LevelMarker,
+    /// Column alignment marker (`<`, `<>`, `>`)
+    AlignMarker,
+    /// Inline emphasis delimiter (`*`, `**`, `***`)
+    EmphasisMarker,
+    /// Raw text content, including surrounding spaces
+    Text,
+    /// Chord symbol (`Am7`, `F#m7b5/C`)
+    Chord,
+    /// No chord (`N.C.`)
+    NoChord,
+    /// Empty beat (`,`)
+    EmptyBeat,
+    /// Beat repeating the previous chord (`*`)
+    HeldBeat,
+    /// Beat separator joining parts of a bar (`_`)
+    BeatSeparator,
+    /// Push marker before a chord (`<`)
+    PushMarker,
+    /// Accent marker before a chord (`<>`)
+    AccentMarker,
+    /// Ghost chord marker (`?`)
+    GhostMarker,
+    /// Repeat previous bar (`%`)
+    RepeatBar,
+    /// Repeat group delimiters (`(` and `)`)
+    GroupOpen,
+    GroupClose,
+    /// Repeat count after a group (`4x`)
+    RepeatCount,
+    /// Numbered ending (`1.`)
+    Ending,
+    /// Fermata keyword
+    Fermata,
+    /// Quoted annotation (`"text"`)
+    Annotation,
+
+    // Nodes
+    /// The whole document
+    Chart,
+    /// A single line with its level prefix and columns
+    Line,
+    /// An alignment zone, optionally introduced by an alignment marker
+    Column,
+    /// A styled run of text, optionally wrapped in emphasis markers
+    Span,
+    /// A line of chords
+    ChordLine,
+    /// A repeat group within a chord line
+    Group,
+    /// A bar of beats
+    Bar,
+    /// A chord or `N.C.` with its push, accent and ghost markers
+    Hit,
+}
+
+/// A leaf of the syntax tree holding a slice of the original source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub text: String,
+    /// Byte range of the token in the source
+    pub span: Range<usize>,
+}
+
+impl SyntaxToken {
+    pub fn new(kind: SyntaxKind, text: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            span,
+        }
+    }
+}
+
+/// An interior node of the syntax tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    /// Byte range covered by the node's children
+    pub span: Range<usize>,
+    pub children: Vec<SyntaxElement>,
+}
+
+/// Byte ranges of the pieces of one chart line, parallel to the lowered `Line`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineSource {
+    /// The whole line
+    pub span: Range<usize>,
+    /// Left, center and right columns, each covering the text of every zone aligned that way
+    pub columns: [Option<Range<usize>>; 3],
+    /// One range per chord item of a chord line
+    pub items: Vec<Range<usize>>,
+}
+
+/// Either a node or a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new(kind: SyntaxKind, span: Range<usize>, children: Vec<SyntaxElement>) -> Self {
+        Self {
+            kind,
+            span,
+            children,
+        }
+    }
+
+    /// Child nodes in source order
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Direct child tokens in source order
+    pub fn child_tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    /// All tokens below this node in source order
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// The exact source text covered by this node
+    pub fn text(&self) -> String {
+        self.tokens().iter().map(|token| token.text.as_str()).collect()
+    }
+}
+
+/// A lossless syntax tree: every byte of the source belongs to exactly one token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree {
+    pub root: SyntaxNode,
+}
+
+impl SyntaxTree {
+    pub fn new(root: SyntaxNode) -> Self {
+        Self { root }
+    }
+
+    /// Line nodes in source order
+    pub fn lines(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.root.child_nodes()
+    }
+
+    /// Re-emit the source, replacing the text of every token for which `edit` returns a value.
+    ///
+    /// Tokens left untouched keep their original text, so whitespace and layout are preserved.
+    pub fn rewrite(&self, mut edit: impl FnMut(&SyntaxToken) -> Option<String>) -> String {
+        let mut output = String::new();
+        for token in self.root.tokens() {
+            match edit(token) {
+                Some(replacement) => output.push_str(&replacement),
+                None => output.push_str(&token.text),
+            }
+        }
+        output
+    }
+
+    /// Derive the chart model from the syntax tree
+    pub fn to_chart(&self) -> Chart {
+        Chart::new(self.lines().map(lower_line).collect())
+    }
+
+    /// Where each line of `to_chart()` comes from, in the same order
+    pub fn line_sources(&self) -> Vec<LineSource> {
+        self.lines().map(line_source).collect()
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.root.tokens() {
+            f.write_str(&token.text)?;
+        }
+        Ok(())
+    }
+}
+
+fn lower_line(node: &SyntaxNode) -> Line {
+    if node.kind == SyntaxKind::ChordLine {
+        return Line::chords(lower_chord_items(node));
+    }
+
+    let level = node
+        .child_tokens()
+        .find(|token| token.kind == SyntaxKind::LevelMarker)
+        .map(|token| match token.text.as_str() {
+            "===" => LineLevel::Header1,
+            "==" => LineLevel::Header2,
+            "=" => LineLevel::Header3,
+            _ => LineLevel::Text,
+        })
+        .unwrap_or(LineLevel::Text);
+
+    let mut line = Line::new(level, Vec::new(), Vec::new(), Vec::new());
+
+    for column in node.child_nodes() {
+        let marker = column
+            .child_tokens()
+            .find(|token| token.kind == SyntaxKind::AlignMarker)
+            .map(|token| token.text.as_str());
+        let target = match marker {
+            Some("<>") => &mut line.center,
+            Some(">") => &mut line.right,
+            _ => &mut line.left,
+        };
+        target.extend(column.child_nodes().map(lower_span));
+    }
+
+    line
+}
+
+fn lower_span(node: &SyntaxNode) -> TextSpan {
+    let mut markers = 0;
+    let mut text = String::new();
+    for token in node.child_tokens() {
+        match token.kind {
+            SyntaxKind::EmphasisMarker => markers = token.text.len(),
+            SyntaxKind::Text => text.push_str(&token.text),
+            _ => {}
+        }
+    }
+
+    let style = match markers {
+        3 => TextStyle::BoldItalic,
+        2 => TextStyle::Bold,
+        1 => TextStyle::Italic,
+        _ => TextStyle::Normal,
+    };
+
+    TextSpan::new(text.trim(), style)
+}
+
+fn line_source(node: &SyntaxNode) -> LineSource {
+    let mut source = LineSource {
+        span: node.span.clone(),
+        columns: [None, None, None],
+        items: Vec::new(),
+    };
+
+    if node.kind == SyntaxKind::ChordLine {
+        source.items = chord_items(node).into_iter().map(|(_, span)| span).collect();
+        return source;
+    }
+
+    for column in node.child_nodes() {
+        let marker = column
+            .child_tokens()
+            .find(|token| token.kind == SyntaxKind::AlignMarker)
+            .map(|token| token.text.as_str());
+        let index = match marker {
+            Some("<>") => 1,
+            Some(">") => 2,
+            _ => 0,
+        };
+        for span in column.child_nodes() {
+            let merged = match source.columns[index].take() {
+                Some(range) => range.start.min(span.span.start)..range.end.max(span.span.end),
+                None => span.span.clone(),
+            };
+            source.columns[index] = Some(merged);
+        }
+    }
+
+    source
+}
+
+fn lower_chord_items(node: &SyntaxNode) -> Vec<ChordItem> {
+    chord_items(node).into_iter().map(|(item, _)| item).collect()
+}
+
+/// Chord items with the range each was lowered from
+fn chord_items(node: &SyntaxNode) -> Vec<(ChordItem, Range<usize>)> {
+    node.children
+        .iter()
+        .filter_map(|child| match child {
+            SyntaxElement::Node(node) => match node.kind {
+                SyntaxKind::Bar => Some((ChordItem::Bar(lower_bar(node)), node.span.clone())),
+                SyntaxKind::Group => Some((ChordItem::Group(lower_group(node)), node.span.clone())),
+                _ => None,
+            },
+            SyntaxElement::Token(token) => {
+                let item = match token.kind {
+                    SyntaxKind::RepeatBar => Some(ChordItem::RepeatBar),
+                    SyntaxKind::Ending => token.text.trim_end_matches('.').parse().ok().map(ChordItem::Ending),
+                    SyntaxKind::Annotation => Some(ChordItem::Annotation(token.text.trim_matches('"').to_string())),
+                    _ => None,
+                };
+                item.map(|item| (item, token.span.clone()))
+            }
+        })
+        .collect()
+}
+
+fn lower_group(node: &SyntaxNode) -> RepeatGroup {
+    let times = node
+        .child_tokens()
+        .find(|token| token.kind == SyntaxKind::RepeatCount)
+        .and_then(|token| token.text.trim_end_matches('x').parse().ok());
+
+    RepeatGroup {
+        items: lower_chord_items(node),
+        times,
+    }
+}
+
+fn lower_bar(node: &SyntaxNode) -> Bar {
+    let mut bar = Bar::new(Vec::new());
+    for child in &node.children {
+        match child {
+            SyntaxElement::Node(hit) => bar.beats.push(lower_hit(hit)),
+            SyntaxElement::Token(token) => match token.kind {
+                // The previous chord keeps sounding through a held beat, as through an empty one
+                SyntaxKind::EmptyBeat | SyntaxKind::HeldBeat => bar.beats.push(Beat::Empty),
+                SyntaxKind::Fermata => bar.fermata = true,
+                _ => {}
+            },
+        }
+    }
+    bar
+}
+
+fn lower_hit(node: &SyntaxNode) -> Beat {
+    let has = |kind| node.child_tokens().any(|token| token.kind == kind);
+    let chord = node
+        .child_tokens()
+        .find(|token| token.kind == SyntaxKind::Chord)
+        .and_then(|token| token.text.parse::<Chord>().ok());
+
+    match chord {
+        Some(chord) => Beat::Chord(ChordHit {
+            chord,
+            push: has(SyntaxKind::PushMarker),
+            accent: has(SyntaxKind::AccentMarker),
+            ghost: has(SyntaxKind::GhostMarker),
+        }),
+        None => Beat::NoChord,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_cst;
+
+    const SOURCE: &str = "  === <Song   Title <>*Composer*   >2024\n\n\t== <Verse 1  \r\n- Piano **only** \n";
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        let tree = parse_cst(SOURCE).unwrap();
+        assert_eq!(tree.to_string(), SOURCE);
+    }
+
+    #[test]
+    fn test_tokens_cover_source() {
+        let tree = parse_cst(SOURCE).unwrap();
+        let mut offset = 0;
+        for token in tree.root.tokens() {
+            assert_eq!(token.span.start, offset);
+            assert_eq!(&SOURCE[token.span.clone()], token.text);
+            offset = token.span.end;
+        }
+        assert_eq!(offset, SOURCE.len());
+    }
+
+    #[test]
+    fn test_to_chart_trims_text() {
+        let chart = parse_cst(SOURCE).unwrap().to_chart();
+        assert_eq!(chart.lines.len(), 3);
+        assert_eq!(chart.lines[0].left[0].text, "Song   Title");
+        assert_eq!(chart.lines[0].center[0].text, "Composer");
+        assert_eq!(chart.lines[0].center[0].style, TextStyle::Italic);
+        assert_eq!(chart.lines[0].right[0].text, "2024");
+        assert_eq!(chart.lines[1].level, LineLevel::Header2);
+        assert_eq!(chart.lines[2].left[1].text, "only");
+        assert_eq!(chart.lines[2].left[1].style, TextStyle::Bold);
+    }
+
+    #[test]
+    fn test_rewrite_preserves_layout() {
+        let tree = parse_cst(SOURCE).unwrap();
+        let renamed = tree.rewrite(|token| {
+            (token.kind == SyntaxKind::Text && token.text.trim() == "Verse 1")
+                .then(|| token.text.replace("Verse 1", "Chorus"))
+        });
+        assert_eq!(renamed, SOURCE.replace("Verse 1", "Chorus"));
+    }
+
+    #[test]
+    fn test_line_sources_match_lowered_lines() {
+        let source = "- <Intro >*Swing*\nAm_G % \"fine\"\n";
+        let tree = parse_cst(source).unwrap();
+        let sources = tree.line_sources();
+        assert_eq!(sources.len(), tree.to_chart().lines.len());
+
+        let [left, center, right] = &sources[0].columns;
+        assert_eq!(&source[left.clone().unwrap()], "Intro ");
+        assert!(center.is_none());
+        assert_eq!(&source[right.clone().unwrap()], "*Swing*");
+
+        let items: Vec<&str> = sources[1].items.iter().map(|span| &source[span.clone()]).collect();
+        assert_eq!(items, ["Am_G", "%", "\"fine\""]);
+    }
+}
@@ -0,0 +1,97 @@
+// Canonical source formatting
+//
+// Re-emits each line from the chart model: one space after the level prefix, explicit
+// alignment markers, single spaces between chord items. Runs of blank lines collapse to one.
+
+use super::{parse_cst, Result};
+use crate::model::{Line, LineLevel, TextSpan, TextStyle};
+
+/// Format chart source in the canonical style; the formatted chart parses to the same model
+pub fn format(input: &str) -> Result<String> {
+    let tree = parse_cst(input)?;
+    let chart = tree.to_chart();
+
+    let mut output = String::new();
+    let mut previous_end = None;
+    for (node, line) in tree.lines().zip(&chart.lines) {
+        // Two or more line breaks between lines leave one blank line
+        if previous_end.is_some_and(|end| input[end..node.span.start].matches('\n').count() > 1) {
+            output.push('\n');
+        }
+        output.push_str(&format_line(line));
+        output.push('\n');
+        previous_end = Some(node.span.end);
+    }
+    Ok(output)
+}
+
+fn format_line(line: &Line) -> String {
+    let prefix = match line.level {
+        LineLevel::Header1 => "===",
+        LineLevel::Header2 => "==",
+        LineLevel::Header3 => "=",
+        LineLevel::Text => "-",
+        LineLevel::Chords => {
+            let items: Vec<String> = line.chords.iter().map(|item| item.to_string()).collect();
+            return items.join(" ");
+        }
+    };
+
+    let columns: Vec<(&str, String)> = [("<", &line.left), ("<>", &line.center), (">", &line.right)]
+        .into_iter()
+        .map(|(marker, spans)| (marker, format_spans(spans)))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+    let zones: Vec<String> = match columns.as_slice() {
+        // A lone left column needs no marker
+        [("<", text)] => vec![text.clone()],
+        columns => columns.iter().map(|(marker, text)| format!("{}{}", marker, text)).collect(),
+    };
+    if zones.is_empty() {
+        return prefix.to_string();
+    }
+    format!("{} {}", prefix, zones.join(" "))
+}
+
+fn format_spans(spans: &[TextSpan]) -> String {
+    spans
+        .iter()
+        .filter(|span| !span.text.is_empty())
+        .map(|span| match span.style {
+            TextStyle::Normal => span.text.clone(),
+            TextStyle::Italic => format!("*{}*", span.text),
+            TextStyle::Bold => format!("**{}**", span.text),
+            TextStyle::BoldItalic => format!("***{}***", span.text),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    const MESSY: &str = "===   <>Song   Title\n\n\n=  <Intro >*Swing*\nAm   G_F   %\n\n-  <left  <>**mid** >right\n(F  1. E  2. Em,, fermata)   2x  \"fine\"\n\n";
+
+    #[test]
+    fn test_format_canonical() {
+        assert_eq!(
+            format(MESSY).unwrap(),
+            "=== <>Song   Title\n\
+             \n\
+             = <Intro >*Swing*\n\
+             Am G_F %\n\
+             \n\
+             - <left <>**mid** >right\n\
+             (F 1. E 2. Em,, fermata) 2x \"fine\"\n"
+        );
+    }
+
+    #[test]
+    fn test_format_preserves_model_and_is_stable() {
+        let formatted = format(MESSY).unwrap();
+        assert_eq!(parse_chart(&formatted).unwrap(), parse_chart(MESSY).unwrap());
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+}
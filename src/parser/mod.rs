@@ -1,8 +1,17 @@
+pub mod cst;
+mod format;
+mod setlist;
+
+pub use format::format;
+pub use setlist::parse_setlist;
+
 use chumsky::prelude::*;
 use chumsky::extra;
-use crate::model::{Chart, Line, LineLevel, TextSpan, TextStyle};
+use crate::model::{Chart, Chord};
+use cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, SyntaxTree};
 use miette::{Diagnostic, SourceSpan};
 use std::fmt;
+use std::ops::Range;
 
 /// Parser error type
 #[derive(Debug, Diagnostic)]
@@ -24,136 +33,381 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+impl ParseError {
+    /// Byte range of the offending input
+    pub fn span(&self) -> Range<usize> {
+        self.span.offset()..self.span.offset() + self.span.len()
+    }
+
+    /// Description of what the parser expected
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+
+    /// One-based line and column (in characters) where the error starts
+    pub fn line_col(&self) -> (usize, usize) {
+        let before = &self.src[..self.span.offset().min(self.src.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, column)
+    }
+}
+
 /// Result type alias for parser operations
 pub type Result<T> = std::result::Result<T, ParseError>;
 
 /// Parse a complete chart from input text
 pub fn parse_chart(input: &str) -> Result<Chart> {
-    let result = chart_parser().parse(input);
-    match result.into_result() {
-        Ok(lines) => Ok(Chart::new(lines)),
-        Err(errors) => {
-            if let Some(error) = errors.first() {
-                let span = error.span();
-                Err(ParseError {
-                    src: input.to_string(),
-                    span: SourceSpan::new(span.start.into(), span.end - span.start),
-                    help: format!("{}", error),
-                })
-            } else {
-                Err(ParseError {
-                    src: input.to_string(),
-                    span: SourceSpan::new(0.into(), 1),
-                    help: "Unknown parse error".to_string(),
-                })
-            }
+    parse_cst(input).map(|tree| tree.to_chart())
+}
+
+/// Parse input text into a lossless concrete syntax tree
+pub fn parse_cst(input: &str) -> Result<SyntaxTree> {
+    chart_parser()
+        .parse(input)
+        .into_result()
+        .map(SyntaxTree::new)
+        .map_err(|errors| parse_error(input, errors))
+}
+
+/// Convert the first chumsky error into a `ParseError` over `input`
+fn parse_error(input: &str, errors: Vec<Rich<'_, char>>) -> ParseError {
+    if let Some(error) = errors.first() {
+        let span = error.span();
+        let span = unclosed_emphasis(input, span.start).unwrap_or(span.start..span.end);
+        ParseError {
+            src: input.to_string(),
+            span: SourceSpan::new(span.start.into(), span.end - span.start),
+            help: format!("{}", error),
+        }
+    } else {
+        ParseError {
+            src: input.to_string(),
+            span: SourceSpan::new(0.into(), 1),
+            help: "Unknown parse error".to_string(),
         }
     }
 }
 
-fn chart_parser<'a>() -> impl Parser<'a, &'a str, Vec<Line>, extra::Err<Rich<'a, char>>> {
-    line_parser()
-        .padded()
-        .repeated()
-        .collect()
-        .then_ignore(end())
+/// The `*` run left open on the line containing `offset`, which is where an emphasis error
+/// really starts; chumsky only notices at the end of the line
+fn unclosed_emphasis(input: &str, offset: usize) -> Option<Range<usize>> {
+    let offset = offset.min(input.len());
+    let start = input[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    let end = input[offset..].find('\n').map_or(input.len(), |newline| offset + newline);
+    let line = &input[start..end];
+
+    let mut open: Option<Range<usize>> = None;
+    let mut rest = line;
+    while let Some(found) = rest.find('*') {
+        let at = line.len() - rest.len() + found;
+        let count = line[at..].chars().take_while(|&c| c == '*').count();
+        open = match open {
+            Some(run) if run.len() == count => None,
+            Some(run) => Some(run),
+            None => Some(start + at..start + at + count),
+        };
+        rest = &line[at + count..];
+    }
+    open
+}
+
+type Extra<'a> = extra::Err<Rich<'a, char>>;
+
+/// Wrap the input matched by `parser` in a token of the given kind
+fn token<'a, O>(
+    kind: SyntaxKind,
+    parser: impl Parser<'a, &'a str, O, Extra<'a>>,
+) -> impl Parser<'a, &'a str, SyntaxElement, Extra<'a>> {
+    parser.to_slice().map_with(move |text: &str, e| {
+        let span: SimpleSpan = e.span();
+        SyntaxElement::Token(SyntaxToken::new(kind, text, span.start..span.end))
+    })
 }
 
-fn line_parser<'a>() -> impl Parser<'a, &'a str, Line, extra::Err<Rich<'a, char>>> {
-    let header1 = just("===").ignored().to(LineLevel::Header1);
-    let header2 = just("==").ignored().to(LineLevel::Header2);
-    let header3 = just("=").ignored().to(LineLevel::Header3);
-    let text_level = just("-").ignored().to(LineLevel::Text);
+/// Wrap the children produced by `parser` in a node of the given kind
+fn node<'a>(
+    kind: SyntaxKind,
+    parser: impl Parser<'a, &'a str, Vec<SyntaxElement>, Extra<'a>>,
+) -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    parser.map_with(move |children, e| {
+        let span: SimpleSpan = e.span();
+        SyntaxNode::new(kind, span.start..span.end, children)
+    })
+}
 
-    let level = header1.or(header2).or(header3).or(text_level);
+fn whitespace<'a>() -> impl Parser<'a, &'a str, Option<SyntaxElement>, Extra<'a>> {
+    token(
+        SyntaxKind::Whitespace,
+        any().filter(|c: &char| c.is_whitespace()).repeated().at_least(1),
+    )
+    .or_not()
+}
 
-    (level.padded())
-        .then(columns_parser())
-        .map(|(level, (left, center, right))| Line {
-            level,
-            left,
-            center,
-            right,
-        })
+/// Spaces and tabs only, for whitespace that must not cross a line break
+fn inline_whitespace<'a>() -> impl Parser<'a, &'a str, SyntaxElement, Extra<'a>> {
+    token(SyntaxKind::Whitespace, one_of(" \t").repeated().at_least(1))
+}
+
+fn chart_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    let padded_line = whitespace()
+        .then(line_parser().or(chord_line_parser()))
+        .then(whitespace())
+        .map(|((before, line), after)| {
+            let mut elements: Vec<SyntaxElement> = before.into_iter().collect();
+            elements.push(SyntaxElement::Node(line));
+            elements.extend(after);
+            elements
+        });
+
+    node(
+        SyntaxKind::Chart,
+        padded_line
+            .repeated()
+            .collect::<Vec<_>>()
+            .map(|lines| lines.into_iter().flatten().collect()),
+    )
+    .then_ignore(end())
+}
+
+fn line_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    let level = token(
+        SyntaxKind::LevelMarker,
+        just("===").or(just("==")).or(just("=")).or(just("-")),
+    );
+
+    node(
+        SyntaxKind::Line,
+        level
+            .then(inline_whitespace().or_not())
+            .then(columns_parser())
+            .map(|((level, space), columns)| {
+                let mut elements = vec![level];
+                elements.extend(space);
+                elements.extend(columns.into_iter().map(SyntaxElement::Node));
+                elements
+            }),
+    )
 }
 
-fn columns_parser<'a>() -> impl Parser<'a, &'a str, (Vec<TextSpan>, Vec<TextSpan>, Vec<TextSpan>), extra::Err<Rich<'a, char>>> {
+/// A column introduced by the given alignment marker
+fn marked_column<'a>(marker: &'static str) -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    node(
+        SyntaxKind::Column,
+        token(SyntaxKind::AlignMarker, just(marker))
+            .then(styled_text_parser().repeated().collect::<Vec<_>>())
+            .map(|(marker, spans)| {
+                let mut elements = vec![marker];
+                elements.extend(spans.into_iter().map(SyntaxElement::Node));
+                elements
+            }),
+    )
+}
+
+fn columns_parser<'a>() -> impl Parser<'a, &'a str, Vec<SyntaxNode>, Extra<'a>> {
     // Try center marker first (since <> starts with <, it must be checked before <)
-    let with_center = just("<>")
-        .ignore_then(styled_text_parser().repeated().collect::<Vec<_>>())
-        .then(just(">").ignore_then(styled_text_parser().repeated().collect::<Vec<_>>()).or_not())
+    let with_center = marked_column("<>")
+        .then(marked_column(">").or_not())
         .map(|(center, right)| {
-            (Vec::new(), center, right.unwrap_or_default())
+            std::iter::once(center).chain(right).collect::<Vec<_>>()
         });
     
     // Try left marker with optional center and right
-    let with_left = just("<")
-        .ignore_then(styled_text_parser().repeated().collect::<Vec<_>>())
-        .then(just("<>").ignore_then(styled_text_parser().repeated().collect::<Vec<_>>()).or_not())
-        .then(just(">").ignore_then(styled_text_parser().repeated().collect::<Vec<_>>()).or_not())
+    let with_left = marked_column("<")
+        .then(marked_column("<>").or_not())
+        .then(marked_column(">").or_not())
         .map(|((left, center), right)| {
-            (left, center.unwrap_or_default(), right.unwrap_or_default())
+            std::iter::once(left).chain(center).chain(right).collect::<Vec<_>>()
         });
     
     // Try right marker only (starts with >)
-    let with_right = just(">")
-        .ignore_then(styled_text_parser().repeated().collect::<Vec<_>>())
-        .map(|right| {
-            (Vec::new(), Vec::new(), right)
-        });
+    let with_right = marked_column(">").map(|right| vec![right]);
     
     // No markers - just left content
-    let no_markers = styled_text_parser()
-        .repeated()
-        .collect::<Vec<_>>()
-        .map(|left| (left, Vec::new(), Vec::new()));
+    let no_markers = node(
+        SyntaxKind::Column,
+        styled_text_parser()
+            .repeated()
+            .collect::<Vec<_>>()
+            .map(|spans| spans.into_iter().map(SyntaxElement::Node).collect()),
+    )
+    .map(|left| vec![left]);
     
     with_center.or(with_left).or(with_right).or(no_markers)
 }
 
-fn styled_text_parser<'a>() -> impl Parser<'a, &'a str, TextSpan, extra::Err<Rich<'a, char>>> {
-    let bold_italic = just("***")
-        .ignored()
-        .then(none_of("*").repeated().at_least(1).collect::<String>())
-        .then_ignore(just("***"))
-        .map(|(_, text)| TextSpan {
-            text: text.trim().to_string(),
-            style: TextStyle::BoldItalic,
-        });
+fn styled_text_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    let emphasized = |marker: &'static str, content| {
+        token(SyntaxKind::EmphasisMarker, just(marker))
+            .then(token(SyntaxKind::Text, content))
+            .then(token(SyntaxKind::EmphasisMarker, just(marker)))
+            .map(|((open, text), close)| vec![open, text, close])
+    };
 
-    let bold = just("**")
-        .ignored()
-        .then(none_of("*").repeated().at_least(1).collect::<String>())
-        .then_ignore(just("**"))
-        .map(|(_, text)| TextSpan {
-            text: text.trim().to_string(),
-            style: TextStyle::Bold,
-        });
+    let bold_italic = emphasized("***", none_of("*").repeated().at_least(1));
+    let bold = emphasized("**", none_of("*").repeated().at_least(1));
+    let italic = emphasized("*", none_of("*<>\n").repeated().at_least(1));
 
-    let italic = just("*")
-        .ignored()
-        .then(none_of("*<>\n").repeated().at_least(1).collect::<String>())
-        .then_ignore(just("*"))
-        .map(|(_, text)| TextSpan {
-            text: text.trim().to_string(),
-            style: TextStyle::Italic,
+    let plain = token(SyntaxKind::Text, none_of("<>*\n").repeated().at_least(1))
+        .map(|text| vec![text]);
+
+    node(SyntaxKind::Span, bold_italic.or(bold).or(italic).or(plain))
+}
+
+fn chord_line_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    node(
+        SyntaxKind::ChordLine,
+        separated_items(|| group_parser().map(SyntaxElement::Node).or(chord_item())),
+    )
+}
+
+fn group_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    let count = inline_whitespace()
+        .or_not()
+        .then(token(SyntaxKind::RepeatCount, text::digits(10).at_least(1).then(just("x"))));
+
+    node(
+        SyntaxKind::Group,
+        token(SyntaxKind::GroupOpen, just("("))
+            .then(inline_whitespace().or_not())
+            .then(separated_items(chord_item))
+            .then(inline_whitespace().or_not())
+            .then(token(SyntaxKind::GroupClose, just(")")))
+            .then(count.or_not())
+            .map(|(((((open, before), items), after), close), count)| {
+                let mut elements = vec![open];
+                elements.extend(before);
+                elements.extend(items);
+                elements.extend(after);
+                elements.push(close);
+                if let Some((space, count)) = count {
+                    elements.extend(space);
+                    elements.push(count);
+                }
+                elements
+            }),
+    )
+}
+
+/// A single element of a chord line, other than a repeat group
+fn chord_item<'a>() -> impl Parser<'a, &'a str, SyntaxElement, Extra<'a>> {
+    let ending = token(
+        SyntaxKind::Ending,
+        text::digits(10).at_least(1).then(just(".")),
+    );
+    let repeat_bar = token(SyntaxKind::RepeatBar, just("%"));
+    let annotation = token(
+        SyntaxKind::Annotation,
+        just("\"").then(none_of("\"\n").repeated()).then(just("\"")),
+    );
+
+    ending
+        .or(repeat_bar)
+        .or(annotation)
+        .or(bar_parser().map(SyntaxElement::Node))
+}
+
+fn bar_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    // Beats joined by `_` (optionally spaced) belong to the same bar
+    let joined = inline_whitespace()
+        .or_not()
+        .then(token(SyntaxKind::BeatSeparator, just("_")))
+        .then(inline_whitespace().or_not())
+        .then(beat_slots())
+        .map(|(((before, separator), after), slots)| {
+            let mut elements: Vec<SyntaxElement> = before.into_iter().collect();
+            elements.push(separator);
+            elements.extend(after);
+            elements.extend(slots);
+            elements
         });
 
-    let plain = none_of("<>*\n")
+    let fermata = inline_whitespace().then(token(SyntaxKind::Fermata, just("fermata")));
+
+    node(
+        SyntaxKind::Bar,
+        beat_slots()
+            .then(joined.repeated().collect::<Vec<_>>())
+            .then(fermata.or_not())
+            .map(|((mut elements, joined), fermata)| {
+                elements.extend(joined.into_iter().flatten());
+                if let Some((space, fermata)) = fermata {
+                    elements.push(space);
+                    elements.push(fermata);
+                }
+                elements
+            }),
+    )
+}
+
+/// One or more chords, empty beats (`,`) and held beats (`*`) written without spaces
+fn beat_slots<'a>() -> impl Parser<'a, &'a str, Vec<SyntaxElement>, Extra<'a>> {
+    token(SyntaxKind::EmptyBeat, just(","))
+        .or(token(SyntaxKind::HeldBeat, just("*")))
+        .or(hit_parser().map(SyntaxElement::Node))
         .repeated()
         .at_least(1)
-        .collect::<String>()
-        .map(|text| TextSpan {
-            text: text.trim().to_string(),
-            style: TextStyle::Normal,
-        });
+        .collect()
+}
 
-    bold_italic.or(bold).or(italic).or(plain)
+fn hit_parser<'a>() -> impl Parser<'a, &'a str, SyntaxNode, Extra<'a>> {
+    let accent = token(SyntaxKind::AccentMarker, just("<>")).then(inline_whitespace().or_not());
+    let chord = token(SyntaxKind::NoChord, just("N.C.")).or(token(SyntaxKind::Chord, chord_symbol()));
+
+    node(
+        SyntaxKind::Hit,
+        accent
+            .or_not()
+            .then(token(SyntaxKind::PushMarker, just("<")).or_not())
+            .then(chord)
+            .then(token(SyntaxKind::GhostMarker, just("?")).or_not())
+            .map(|(((accent, push), chord), ghost)| {
+                let mut elements = Vec::new();
+                if let Some((accent, space)) = accent {
+                    elements.push(accent);
+                    elements.extend(space);
+                }
+                elements.extend(push);
+                elements.push(chord);
+                elements.extend(ghost);
+                elements
+            }),
+    )
+}
+
+/// One or more items separated by inline whitespace, keeping the whitespace tokens
+fn separated_items<'a, P>(item: impl Fn() -> P) -> impl Parser<'a, &'a str, Vec<SyntaxElement>, Extra<'a>>
+where
+    P: Parser<'a, &'a str, SyntaxElement, Extra<'a>>,
+{
+    item()
+        .then(inline_whitespace().then(item()).repeated().collect::<Vec<_>>())
+        .map(|(first, rest)| {
+            let mut elements = vec![first];
+            for (space, item) in rest {
+                elements.push(space);
+                elements.push(item);
+            }
+            elements
+        })
+}
+
+/// A chord symbol: note letter followed by anything up to a delimiter, validated as a `Chord`
+fn chord_symbol<'a>() -> impl Parser<'a, &'a str, Chord, Extra<'a>> {
+    one_of("ABCDEFG")
+        .then(none_of(" \t\r\n,*_?()\"%<>").repeated())
+        .to_slice()
+        .try_map(|symbol: &str, span| {
+            symbol
+                .parse::<Chord>()
+                .map_err(|error| Rich::custom(span, error))
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::{Beat, ChordItem, LineLevel, TextStyle};
 
     #[test]
     fn test_parse_empty() {
@@ -237,4 +491,60 @@ mod tests {
         let error = result.unwrap_err();
         assert!(!error.help.is_empty(), "Expected error to have help text");
     }
+
+    #[test]
+    fn test_parse_error_points_at_unclosed_marker() {
+        let error = parse_chart("=== Title\n- *Unclosed italic marker").unwrap_err();
+        assert_eq!(error.span(), 12..13);
+        assert_eq!(error.line_col(), (2, 3));
+    }
+
+    #[test]
+    fn test_parse_chord_lines() {
+        let input = "= Verse 1\n(Am,,, ,<Em,, Em, _ G?) 4x\nF % 1. N.C. fermata";
+
+        let chart = parse_chart(input).unwrap();
+        assert_eq!(chart.lines.len(), 3);
+        assert_eq!(chart.lines[1].level, LineLevel::Chords);
+
+        let ChordItem::Group(group) = &chart.lines[1].chords[0] else {
+            panic!("expected a repeat group");
+        };
+        assert_eq!(group.times, Some(4));
+        assert_eq!(group.items.len(), 3);
+
+        let ChordItem::Bar(bar) = &group.items[1] else {
+            panic!("expected a bar");
+        };
+        assert_eq!(bar.beats.len(), 4);
+        assert_eq!(bar.beats[0], Beat::Empty);
+        let Beat::Chord(hit) = &bar.beats[1] else {
+            panic!("expected a chord");
+        };
+        assert!(hit.push);
+        assert_eq!(hit.chord.to_string(), "Em");
+
+        let items = &chart.lines[2].chords;
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[1], ChordItem::RepeatBar);
+        assert_eq!(items[2], ChordItem::Ending(1));
+        let ChordItem::Bar(bar) = &items[3] else {
+            panic!("expected a bar");
+        };
+        assert_eq!(bar.beats, vec![Beat::NoChord]);
+        assert!(bar.fermata);
+    }
+
+    #[test]
+    fn test_parse_held_beats() {
+        let chart = parse_chart("Am * G_*\n").unwrap();
+        let bars: Vec<String> = chart.lines[0].chords.iter().map(|item| item.to_string()).collect();
+        assert_eq!(bars, ["Am", ",", "G,"]);
+    }
+
+    #[test]
+    fn test_parse_invalid_chord_returns_error() {
+        assert!(parse_chart("Am H7").is_err());
+        assert!(parse_chart("C/X").is_err());
+    }
 }
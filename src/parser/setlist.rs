@@ -0,0 +1,153 @@
+// Setlist manifest parser
+//
+// One chart path per line, optionally followed by `| key: Bb` and `| notes: ...` fields.
+// A `title:` line names the setlist; blank lines and `#` comments are ignored, including
+// comments after an entry.
+
+use super::{parse_error, Extra, Result};
+use crate::model::{Key, Setlist, SetlistEntry};
+use chumsky::prelude::*;
+
+/// A single meaningful line of the manifest
+enum Directive {
+    Title(String),
+    Entry(SetlistEntry),
+}
+
+/// A `| name: value` field following an entry's path
+enum Field {
+    Key(Key),
+    Notes(String),
+}
+
+/// Parse a setlist manifest
+pub fn parse_setlist(input: &str) -> Result<Setlist> {
+    let directives = setlist_parser()
+        .parse(input)
+        .into_result()
+        .map_err(|errors| parse_error(input, errors))?;
+
+    let mut setlist = Setlist::default();
+    for directive in directives {
+        match directive {
+            Directive::Title(title) => setlist.title = Some(title),
+            Directive::Entry(entry) => setlist.entries.push(entry),
+        }
+    }
+    Ok(setlist)
+}
+
+fn setlist_parser<'a>() -> impl Parser<'a, &'a str, Vec<Directive>, Extra<'a>> {
+    let directive = inline_whitespace().ignore_then(
+        comment()
+            .map(|_| None)
+            .or(title().map(Some))
+            .or(entry().map(Some))
+            .or_not()
+            .map(Option::flatten),
+    );
+
+    directive
+        .separated_by(just('\r').or_not().then(just('\n')))
+        .collect::<Vec<_>>()
+        .map(|directives| directives.into_iter().flatten().collect())
+        .then_ignore(end())
+}
+
+fn inline_whitespace<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
+    one_of(" \t").repeated()
+}
+
+fn comment<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
+    just('#').then(none_of("\r\n").repeated()).ignored()
+}
+
+/// A comment after an entry; the `#` must follow whitespace, since keys use it for sharps
+fn trailing_comment<'a>() -> impl Parser<'a, &'a str, (), Extra<'a>> {
+    one_of(" \t").repeated().at_least(1).then(comment()).ignored()
+}
+
+/// Text up to the next field separator, comment or line end, trimmed
+fn value<'a>() -> impl Parser<'a, &'a str, &'a str, Extra<'a>> {
+    none_of("|\r\n")
+        .and_is(trailing_comment().not())
+        .repeated()
+        .to_slice()
+        .map(str::trim)
+}
+
+fn title<'a>() -> impl Parser<'a, &'a str, Directive, Extra<'a>> {
+    just("title:")
+        .ignore_then(none_of("\r\n").repeated().to_slice())
+        .map(|title: &str| Directive::Title(title.trim().to_string()))
+}
+
+fn entry<'a>() -> impl Parser<'a, &'a str, Directive, Extra<'a>> {
+    let path = none_of("|#\r\n")
+        .repeated()
+        .at_least(1)
+        .to_slice()
+        .map(str::trim);
+
+    path.then(just('|').ignore_then(field()).repeated().collect::<Vec<_>>())
+        .then_ignore(inline_whitespace().then(comment()).or_not())
+        .map(|(path, fields)| {
+            let mut entry = SetlistEntry::new(path);
+            for field in fields {
+                match field {
+                    Field::Key(key) => entry.key = Some(key),
+                    Field::Notes(notes) => entry.notes = Some(notes),
+                }
+            }
+            Directive::Entry(entry)
+        })
+}
+
+fn field<'a>() -> impl Parser<'a, &'a str, Field, Extra<'a>> {
+    // A key is written as its tonic, with a trailing `m` for minor keys
+    let key = just("key:").then(inline_whitespace()).ignore_then(value().try_map(|text: &str, span| {
+        text.parse::<Key>()
+            .map(Field::Key)
+            .map_err(|_| Rich::custom(span, format!("invalid key '{}'", text)))
+    }));
+    let notes = just("notes:")
+        .then(inline_whitespace())
+        .ignore_then(value().map(|text| Field::Notes(text.to_string())));
+
+    inline_whitespace().ignore_then(key.or(notes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Accidental, Note, NoteName};
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_setlist() {
+        let input = "# Friday gig\ntitle: Friday at the Club\n\nsongs/blue-bossa.chords | key: Bb | notes: count in 4\n  songs/autumn-leaves.chords\n";
+        let setlist = parse_setlist(input).unwrap();
+
+        assert_eq!(setlist.title.as_deref(), Some("Friday at the Club"));
+        assert_eq!(setlist.entries.len(), 2);
+        assert_eq!(setlist.entries[0].path, Path::new("songs/blue-bossa.chords"));
+        assert_eq!(setlist.entries[0].key, Some(Key::new(Note::new(NoteName::B, Accidental::Flat), false)));
+        assert_eq!(setlist.entries[0].notes.as_deref(), Some("count in 4"));
+        assert_eq!(setlist.entries[1].path, Path::new("songs/autumn-leaves.chords"));
+        assert_eq!(setlist.entries[1].key, None);
+    }
+
+    #[test]
+    fn test_parse_setlist_trailing_comments() {
+        let setlist = parse_setlist("one.chords # opener\ntwo.chords | key: F#m # capo 2\n").unwrap();
+        assert_eq!(setlist.entries[0].path, Path::new("one.chords"));
+        assert_eq!(setlist.entries[1].key.map(|key| key.to_string()).as_deref(), Some("F#m"));
+    }
+
+    #[test]
+    fn test_parse_setlist_invalid_key() {
+        let error = parse_setlist("song.chords | key: H\n").unwrap_err();
+        assert_eq!(error.line_col(), (1, 20));
+        assert!(error.help().contains("invalid key 'H'"));
+    }
+}
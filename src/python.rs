@@ -0,0 +1,291 @@
+// Python bindings
+//
+// Build the extension with `maturin develop` (see `pyproject.toml`). Charts are returned as
+// read-only Python objects mirroring the model; chord lines keep their items as source text.
+
+use crate::model::{self, LineLevel, TextStyle};
+use crate::parser;
+use crate::render::{ChordSymbols, FontStyle, SvgConfig};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+create_exception!(
+    chord_script,
+    ParseError,
+    PyValueError,
+    "Chart source that does not parse; `help`, `start`, `end`, `line` and `column` locate the error."
+);
+
+/// A styled span of text
+#[pyclass(frozen, module = "chord_script")]
+#[derive(Clone)]
+pub struct TextSpan {
+    #[pyo3(get)]
+    text: String,
+    /// `normal`, `bold`, `italic` or `bold_italic`
+    #[pyo3(get)]
+    style: &'static str,
+}
+
+#[pymethods]
+impl TextSpan {
+    fn __repr__(&self) -> String {
+        format!("TextSpan({:?}, {:?})", self.text, self.style)
+    }
+}
+
+/// A chart line: three columns of text, or the items of a chord line
+#[pyclass(frozen, module = "chord_script")]
+#[derive(Clone)]
+pub struct Line {
+    /// `header1`, `header2`, `header3`, `text` or `chords`
+    #[pyo3(get)]
+    level: &'static str,
+    #[pyo3(get)]
+    left: Vec<TextSpan>,
+    #[pyo3(get)]
+    center: Vec<TextSpan>,
+    #[pyo3(get)]
+    right: Vec<TextSpan>,
+    /// Bars, repeats, endings and annotations as written, e.g. `["Am_G", "%", "(F E) 2x"]`
+    #[pyo3(get)]
+    chords: Vec<String>,
+}
+
+#[pymethods]
+impl Line {
+    fn __repr__(&self) -> String {
+        format!("Line(level={:?})", self.level)
+    }
+}
+
+/// A parsed chart
+#[pyclass(frozen, module = "chord_script")]
+pub struct Chart {
+    #[pyo3(get)]
+    lines: Vec<Line>,
+    chart: model::Chart,
+}
+
+#[pymethods]
+impl Chart {
+    /// Text of the first level 1 header, if any
+    #[getter]
+    fn title(&self) -> Option<String> {
+        self.chart.title()
+    }
+
+    /// Text of the first level 2 header, if any
+    #[getter]
+    fn subtitle(&self) -> Option<String> {
+        self.chart.subtitle()
+    }
+
+    fn __repr__(&self) -> String {
+        let title = self.chart.title().map_or("None".to_string(), |title| format!("{:?}", title));
+        format!("Chart(title={}, lines={})", title, self.lines.len())
+    }
+}
+
+impl From<model::Chart> for Chart {
+    fn from(chart: model::Chart) -> Self {
+        let spans = |spans: &[model::TextSpan]| {
+            spans
+                .iter()
+                .map(|span| TextSpan {
+                    text: span.text.clone(),
+                    style: match span.style {
+                        TextStyle::Normal => "normal",
+                        TextStyle::Bold => "bold",
+                        TextStyle::Italic => "italic",
+                        TextStyle::BoldItalic => "bold_italic",
+                    },
+                })
+                .collect()
+        };
+        let lines = chart
+            .lines
+            .iter()
+            .map(|line| Line {
+                level: match line.level {
+                    LineLevel::Header1 => "header1",
+                    LineLevel::Header2 => "header2",
+                    LineLevel::Header3 => "header3",
+                    LineLevel::Text => "text",
+                    LineLevel::Chords => "chords",
+                },
+                left: spans(&line.left),
+                center: spans(&line.center),
+                right: spans(&line.right),
+                chords: line.chords.iter().map(|item| item.to_string()).collect(),
+            })
+            .collect();
+        Self { lines, chart }
+    }
+}
+
+/// Parse chart source, raising `ParseError` on invalid input
+#[pyfunction]
+fn parse_chart(py: Python<'_>, source: &str) -> PyResult<Chart> {
+    parser::parse_chart(source)
+        .map(Chart::from)
+        .map_err(|error| parse_error(py, source, &error))
+}
+
+/// SVG renderer; keyword arguments override `SvgConfig` fields
+///
+/// Layout: `width`, `height`, `margin_horizontal`, `margin_vertical` (points), `font_family`,
+/// `bars_per_row`, `chord_symbols` (`"spelled"` or `"jazz"`) and `slash_notation`.
+/// Per level (`header1`, `header2`, `header3`, `text`): `<level>_size`, `<level>_weight` and
+/// `<level>_line_height`, e.g. `SvgGenerator(header1_size=24, font_family="serif")`.
+#[pyclass(frozen, module = "chord_script")]
+pub struct SvgGenerator {
+    config: SvgConfig,
+}
+
+#[pymethods]
+impl SvgGenerator {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    fn new(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut config = SvgConfig::default();
+        for (key, value) in kwargs.into_iter().flatten() {
+            let key: String = key.extract()?;
+            set_option(&mut config, &key, &value)?;
+        }
+        Ok(Self { config })
+    }
+
+    /// Render a chart to an SVG document string
+    fn render(&self, chart: &Chart) -> String {
+        crate::render::SvgGenerator::new(self.config.clone()).render(&chart.chart)
+    }
+}
+
+fn set_option(config: &mut SvgConfig, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+    match key {
+        "width" => config.layout.width = value.extract()?,
+        "height" => config.layout.height = value.extract()?,
+        "margin_horizontal" => config.layout.margin_horizontal = value.extract()?,
+        "margin_vertical" => config.layout.margin_vertical = value.extract()?,
+        "font_family" => config.font_family = value.extract()?,
+        "bars_per_row" => config.bars_per_row = value.extract()?,
+        "slash_notation" => config.slash_notation = value.extract()?,
+        "chord_symbols" => {
+            config.chord_symbols = match value.extract::<String>()?.as_str() {
+                "spelled" => ChordSymbols::Spelled,
+                "jazz" => ChordSymbols::Jazz,
+                other => return Err(PyValueError::new_err(format!("unknown chord symbol style '{}'", other))),
+            }
+        }
+        _ => {
+            let font = key.rsplit_once('_').and_then(|(level, field)| {
+                // `line_height` is the only field name with an underscore
+                let (level, field) = match level.strip_suffix("_line") {
+                    Some(level) if field == "height" => (level, "line_height"),
+                    _ => (level, field),
+                };
+                font_style(config, level).map(|font| (font, field))
+            });
+            match font {
+                Some((font, "size")) => font.size = value.extract()?,
+                Some((font, "weight")) => font.weight = value.str()?.to_string(),
+                Some((font, "line_height")) => font.line_height = value.extract()?,
+                _ => return Err(PyTypeError::new_err(format!("unknown SvgGenerator option '{}'", key))),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn font_style<'a>(config: &'a mut SvgConfig, level: &str) -> Option<&'a mut FontStyle> {
+    match level {
+        "header1" => Some(&mut config.header1),
+        "header2" => Some(&mut config.header2),
+        "header3" => Some(&mut config.header3),
+        "text" => Some(&mut config.text),
+        _ => None,
+    }
+}
+
+/// `ParseError` carrying the location as attributes; `start` and `end` count characters, as
+/// Python string indices do
+fn parse_error(py: Python<'_>, source: &str, error: &parser::ParseError) -> PyErr {
+    let (line, column) = error.line_col();
+    let span = error.span();
+    let characters = |offset: usize| source[..offset.min(source.len())].chars().count();
+    let exception = ParseError::new_err(format!("{}:{}: {}", line, column, error.help()));
+    let value = exception.value(py);
+    let located = value
+        .setattr("help", error.help())
+        .and_then(|()| value.setattr("start", characters(span.start)))
+        .and_then(|()| value.setattr("end", characters(span.end)))
+        .and_then(|()| value.setattr("line", line))
+        .and_then(|()| value.setattr("column", column));
+    if let Err(error) = located {
+        return error;
+    }
+    exception
+}
+
+#[pymodule]
+fn chord_script(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(parse_chart, module)?)?;
+    module.add_class::<Chart>()?;
+    module.add_class::<Line>()?;
+    module.add_class::<TextSpan>()?;
+    module.add_class::<SvgGenerator>()?;
+    module.add("ParseError", module.py().get_type::<ParseError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_options() {
+        pyo3::prepare_freethreaded_python();
+        let mut config = SvgConfig::default();
+        Python::with_gil(|py| {
+            let value = 24.0f64.into_pyobject(py).unwrap().into_any();
+            set_option(&mut config, "header1_size", &value).unwrap();
+            set_option(&mut config, "text_line_height", &value).unwrap();
+            set_option(&mut config, "width", &value).unwrap();
+            assert!(set_option(&mut config, "header4_size", &value).is_err());
+        });
+
+        assert_eq!(config.header1.size, 24.0);
+        assert_eq!(config.text.line_height, 24.0);
+        assert_eq!(config.layout.width, 24.0);
+    }
+
+    #[test]
+    fn test_parse_chart_objects_and_errors() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let chart = parse_chart(py, "=== <>Song\n- <Play *soft*\nAm_G %\n").unwrap();
+            assert_eq!(chart.title().as_deref(), Some("Song"));
+            assert_eq!(chart.lines[1].level, "text");
+            assert_eq!(chart.lines[1].left[1].style, "italic");
+            assert_eq!(chart.lines[2].chords, ["Am_G", "%"]);
+
+            let source = "= 2011 • 𝄞\n(Am G\n";
+            let Err(error) = parse_chart(py, source) else {
+                panic!("unclosed group should not parse");
+            };
+            assert!(error.is_instance_of::<ParseError>(py));
+            assert!(error.is_instance_of::<PyValueError>(py));
+            let expected = parser::parse_chart(source).unwrap_err();
+            let span: (usize, usize) = (
+                error.value(py).getattr("start").unwrap().extract().unwrap(),
+                error.value(py).getattr("end").unwrap().extract().unwrap(),
+            );
+            // `•` and `𝄞` are one character each but three and four bytes
+            let bytes = expected.span();
+            assert_eq!(span, (bytes.start - 5, bytes.end - 5));
+        });
+    }
+}
@@ -0,0 +1,317 @@
+// Interchangeable render backends
+//
+// Every output format implements `Renderer`, so callers (including the CLI) can pick a backend
+// by name from a `Registry` instead of matching on concrete generators. Library users can
+// register their own backends next to the built-in ones.
+
+use super::{HtmlConfig, HtmlGenerator, SvgConfig, SvgGenerator, TextConfig, TextGenerator, STYLESHEET};
+use crate::model::{Chart, Page};
+use miette::Diagnostic;
+use std::cell::OnceCell;
+use thiserror::Error;
+
+/// Error rendering a chart
+#[derive(Debug, Error, Diagnostic)]
+pub enum RenderError {
+    #[cfg(feature = "pdf")]
+    #[error("failed to convert the chart to PDF: {0}")]
+    #[diagnostic(code(render::pdf))]
+    Pdf(#[from] svg2pdf::usvg::Error),
+
+    /// Failure reported by a backend outside this crate
+    #[error("{0}")]
+    #[diagnostic(code(render::backend))]
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Rendered pages: either one document holding them all, or one document per page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Document(Vec<u8>),
+    Pages(Vec<Vec<u8>>),
+}
+
+/// A backend that turns charts into bytes of one output format
+pub trait Renderer {
+    /// File extension of rendered documents, without the dot
+    fn extension(&self) -> &str;
+
+    /// Render a chart to a single document
+    fn render(&self, chart: &Chart) -> Result<Vec<u8>, RenderError>;
+
+    /// Render pages, with their footers, for a setlist
+    fn render_pages(&self, pages: &[Page]) -> Result<Output, RenderError>;
+
+    /// Sheets a page fills; backends that continue long pages onto new ones count them, so
+    /// setlists can number every sheet
+    fn sheets(&self, _page: &Page) -> usize {
+        1
+    }
+
+    /// Supporting files the documents refer to, as (file name, contents) to write alongside
+    fn assets(&self) -> Vec<(String, Vec<u8>)> {
+        Vec::new()
+    }
+}
+
+impl Renderer for SvgGenerator {
+    fn extension(&self) -> &str {
+        "svg"
+    }
+
+    fn render(&self, chart: &Chart) -> Result<Vec<u8>, RenderError> {
+        Ok(SvgGenerator::render(self, chart).into_bytes())
+    }
+
+    /// SVG has no pages, so each laid-out page is its own document
+    fn render_pages(&self, pages: &[Page]) -> Result<Output, RenderError> {
+        Ok(Output::Pages(
+            pages
+                .iter()
+                .flat_map(|page| self.render_sheets(page))
+                .map(String::into_bytes)
+                .collect(),
+        ))
+    }
+
+    fn sheets(&self, page: &Page) -> usize {
+        self.sheet_count(page)
+    }
+}
+
+impl Renderer for TextGenerator {
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn render(&self, chart: &Chart) -> Result<Vec<u8>, RenderError> {
+        Ok(TextGenerator::render(self, chart).into_bytes())
+    }
+
+    /// Pages are separated by form feeds so printers start each chart on a new sheet
+    fn render_pages(&self, pages: &[Page]) -> Result<Output, RenderError> {
+        let text: Vec<String> = pages.iter().map(|page| self.render_page(page)).collect();
+        Ok(Output::Document(text.join("\x0c\n").into_bytes()))
+    }
+}
+
+impl Renderer for HtmlGenerator {
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render(&self, chart: &Chart) -> Result<Vec<u8>, RenderError> {
+        Ok(HtmlGenerator::render(self, chart).into_bytes())
+    }
+
+    fn render_pages(&self, pages: &[Page]) -> Result<Output, RenderError> {
+        Ok(Output::Document(HtmlGenerator::render_pages(self, pages).into_bytes()))
+    }
+
+    /// The default stylesheet, under the name standalone documents link to
+    fn assets(&self) -> Vec<(String, Vec<u8>)> {
+        match self.stylesheet() {
+            Some(name) => vec![(name.to_string(), STYLESHEET.as_bytes().to_vec())],
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "pdf")]
+impl Renderer for super::PdfGenerator {
+    fn extension(&self) -> &str {
+        "pdf"
+    }
+
+    fn render(&self, chart: &Chart) -> Result<Vec<u8>, RenderError> {
+        Ok(super::PdfGenerator::render(self, chart)?)
+    }
+
+    fn render_pages(&self, pages: &[Page]) -> Result<Output, RenderError> {
+        Ok(Output::Document(super::PdfGenerator::render_pages(self, pages)?))
+    }
+
+    fn sheets(&self, page: &Page) -> usize {
+        self.sheet_count(page)
+    }
+}
+
+/// Settings the registry hands to the backends it builds
+#[derive(Debug, Clone, Default)]
+pub struct RenderConfig {
+    /// Page size, fonts and bars per row, for paged backends (SVG and PDF)
+    pub page: SvgConfig,
+    /// Line width and header decoration for plain text
+    pub text: TextConfig,
+    /// Document settings for HTML
+    pub html: HtmlConfig,
+}
+
+/// Builds a backend from the registry's configuration
+type Factory = Box<dyn Fn(&RenderConfig) -> Box<dyn Renderer>>;
+
+/// A registered backend: handed over ready to use, or built on first use
+enum Backend {
+    Built(Box<dyn Renderer>),
+    Lazy(Factory, OnceCell<Box<dyn Renderer>>),
+}
+
+/// Render backends by name, in registration order
+///
+/// Backends are built the first time they are looked up, so a registry only pays for the
+/// ones it uses (the PDF backend loads every system font).
+pub struct Registry {
+    config: RenderConfig,
+    backends: Vec<(String, Backend)>,
+}
+
+impl Registry {
+    /// The built-in backends, configured with `config`: `svg`, `txt`, `html` and (with the
+    /// `pdf` feature) `pdf`
+    pub fn new(config: RenderConfig) -> Self {
+        let mut registry = Self::empty(config);
+        registry.register_with("svg", |config| SvgGenerator::new(config.page.clone()));
+        registry.register_with("txt", |config| TextGenerator::new(config.text.clone()));
+        registry.register_with("html", |config| HtmlGenerator::new(config.html.clone()));
+        #[cfg(feature = "pdf")]
+        registry.register_with("pdf", |config| super::PdfGenerator::new(config.page.clone()));
+        registry
+    }
+
+    /// A registry without any backends
+    pub fn empty(config: RenderConfig) -> Self {
+        Self {
+            config,
+            backends: Vec::new(),
+        }
+    }
+
+    /// Configuration passed to the backends
+    pub fn config(&self) -> &RenderConfig {
+        &self.config
+    }
+
+    /// Add an already built backend, replacing any backend registered under `name`
+    pub fn register(&mut self, name: &str, renderer: impl Renderer + 'static) {
+        self.insert(name, Backend::Built(Box::new(renderer)));
+    }
+
+    /// Add a backend built from the registry's configuration when first looked up, replacing
+    /// any backend registered under `name`
+    pub fn register_with<R, F>(&mut self, name: &str, factory: F)
+    where
+        R: Renderer + 'static,
+        F: Fn(&RenderConfig) -> R + 'static,
+    {
+        let factory: Factory = Box::new(move |config| Box::new(factory(config)));
+        self.insert(name, Backend::Lazy(factory, OnceCell::new()));
+    }
+
+    fn insert(&mut self, name: &str, backend: Backend) {
+        match self.backends.iter_mut().find(|(existing, _)| existing == name) {
+            Some(entry) => entry.1 = backend,
+            None => self.backends.push((name.to_string(), backend)),
+        }
+    }
+
+    /// The backend registered under `name`, building it if this is its first use
+    pub fn get(&self, name: &str) -> Option<&dyn Renderer> {
+        self.backends
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, backend)| match backend {
+                Backend::Built(renderer) => renderer.as_ref(),
+                Backend::Lazy(factory, renderer) => renderer.get_or_init(|| factory(&self.config)).as_ref(),
+            })
+    }
+
+    /// Names of the registered backends
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.backends.iter().map(|(name, _)| name.as_str())
+    }
+}
+
+impl Default for Registry {
+    /// The built-in backends with default configuration
+    fn default() -> Self {
+        Self::new(RenderConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_chart;
+
+    /// A backend outside the crate: one line per chart line
+    struct LineCount;
+
+    impl Renderer for LineCount {
+        fn extension(&self) -> &str {
+            "count"
+        }
+
+        fn render(&self, chart: &Chart) -> Result<Vec<u8>, RenderError> {
+            Ok(chart.lines.len().to_string().into_bytes())
+        }
+
+        fn render_pages(&self, pages: &[Page]) -> Result<Output, RenderError> {
+            pages
+                .iter()
+                .map(|page| self.render(&page.chart))
+                .collect::<Result<_, _>>()
+                .map(Output::Pages)
+        }
+    }
+
+    #[test]
+    fn test_builtin_backends() {
+        let registry = Registry::default();
+        let chart = parse_chart("=== <>Song\nAm G\n").unwrap();
+
+        let svg = registry.get("svg").unwrap();
+        assert_eq!(svg.extension(), "svg");
+        assert!(svg.render(&chart).unwrap().starts_with(b"<svg"));
+        let pages = svg.render_pages(&[Page::new(chart.clone()), Page::new(chart)]).unwrap();
+        assert!(matches!(pages, Output::Pages(pages) if pages.len() == 2));
+
+        assert_eq!(registry.get("html").unwrap().assets()[0].0, "chord-script.css");
+        assert!(registry.get("txt").unwrap().assets().is_empty());
+        assert!(registry.get("docx").is_none());
+    }
+
+    #[test]
+    fn test_register_custom_backend() {
+        let mut registry = Registry::default();
+        registry.register("count", LineCount);
+        registry.register("svg", LineCount);
+
+        let chart = parse_chart("=== <>Song\nAm G\n").unwrap();
+        assert_eq!(registry.get("svg").unwrap().render(&chart).unwrap(), b"2");
+        assert_eq!(registry.names().filter(|&name| name == "svg").count(), 1);
+        assert_eq!(registry.names().last(), Some("count"));
+    }
+
+    #[test]
+    fn test_backends_built_lazily_from_config() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let built = Rc::new(Cell::new(0));
+        let mut config = RenderConfig::default();
+        config.page.bars_per_row = 8;
+        let mut registry = Registry::new(config);
+        let counter = built.clone();
+        registry.register_with("bars", move |config| {
+            counter.set(counter.get() + 1);
+            assert_eq!(config.page.bars_per_row, 8);
+            LineCount
+        });
+        assert_eq!(built.get(), 0);
+
+        let chart = parse_chart("=== <>Song\nAm G\n").unwrap();
+        registry.get("bars").unwrap().render(&chart).unwrap();
+        registry.get("bars").unwrap().render(&chart).unwrap();
+        assert_eq!(built.get(), 1);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::model::{Bar, Beat, Chart, ChordItem, Line, LineLevel, Measure, Page, TextSpan, TextStyle};
+use std::fmt::Write;
+
+/// Default stylesheet for rendered charts, written next to the HTML by the CLI
+pub const STYLESHEET: &str = include_str!("chart.css");
+
+/// Configuration for HTML rendering
+#[derive(Debug, Clone)]
+pub struct HtmlConfig {
+    /// Wrap the chart in a complete document; otherwise emit an `<article>` fragment for embedding
+    pub standalone: bool,
+    /// Stylesheet URL linked from standalone documents
+    pub stylesheet: String,
+}
+
+impl Default for HtmlConfig {
+    fn default() -> Self {
+        Self {
+            standalone: true,
+            stylesheet: "chord-script.css".to_string(),
+        }
+    }
+}
+
+/// HTML generator that renders charts to semantic, reflowable markup
+pub struct HtmlGenerator {
+    config: HtmlConfig,
+}
+
+/// A bar of a chord grid table, with the barlines around it
+#[derive(Default)]
+struct GridCell {
+    content: String,
+    repeat_start: bool,
+    repeat_end: bool,
+    times: Option<u32>,
+}
+
+impl HtmlGenerator {
+    /// Create a new HTML generator with the given configuration
+    pub fn new(config: HtmlConfig) -> Self {
+        Self { config }
+    }
+
+    /// Create a new HTML generator with default configuration
+    pub fn with_defaults() -> Self {
+        Self::new(HtmlConfig::default())
+    }
+
+    /// Stylesheet linked from rendered documents, or `None` for fragments
+    pub fn stylesheet(&self) -> Option<&str> {
+        self.config.standalone.then_some(self.config.stylesheet.as_str())
+    }
+
+    /// Render a Chart to HTML
+    pub fn render(&self, chart: &Chart) -> String {
+        self.render_document(chart.title(), &[render_article(chart, None)])
+    }
+
+    /// Render a page to HTML, with its footer (if any) in a `<footer>` row
+    pub fn render_page(&self, page: &Page) -> String {
+        self.render_document(page.chart.title(), &[render_article(&page.chart, page.footer.as_ref())])
+    }
+
+    /// Render pages as one document, one `<article>` per page, titled after the first page
+    pub fn render_pages(&self, pages: &[Page]) -> String {
+        let articles: Vec<String> = pages
+            .iter()
+            .map(|page| render_article(&page.chart, page.footer.as_ref()))
+            .collect();
+        self.render_document(pages.first().and_then(|page| page.chart.title()), &articles)
+    }
+
+    fn render_document(&self, title: Option<String>, articles: &[String]) -> String {
+        let body = articles.concat();
+        if !self.config.standalone {
+            return body;
+        }
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"{}\">\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape(title.as_deref().unwrap_or("Chart")),
+            escape(&self.config.stylesheet),
+            body
+        )
+    }
+}
+
+fn render_article(chart: &Chart, footer: Option<&Line>) -> String {
+    let mut article = String::from("<article class=\"chart\">\n");
+    for (line, measures) in chart.lines.iter().zip(chart.line_measures()) {
+        if line.level == LineLevel::Chords {
+            article.push_str(&render_grid(&line.chords, &measures));
+        } else {
+            article.push_str(&render_line(line));
+        }
+    }
+    if let Some(footer) = footer {
+        article.push_str(&render_row("footer", footer));
+    }
+    article.push_str("</article>\n");
+    article
+}
+
+/// Headers become `<h1>`–`<h3>`, text lines `<p>`; each holds a three-column flex row
+fn render_line(line: &Line) -> String {
+    let tag = match line.level {
+        LineLevel::Header1 => "h1",
+        LineLevel::Header2 => "h2",
+        LineLevel::Header3 => "h3",
+        LineLevel::Text | LineLevel::Chords => "p",
+    };
+    render_row(tag, line)
+}
+
+fn render_row(tag: &str, line: &Line) -> String {
+    let mut html = format!("<{} class=\"row\">", tag);
+    for (class, spans) in [("left", &line.left), ("center", &line.center), ("right", &line.right)] {
+        let _ = write!(html, "<span class=\"{}\">{}</span>", class, render_spans(spans));
+    }
+    let _ = writeln!(html, "</{}>", tag);
+    html
+}
+
+/// Styled spans, separated by spaces since the parser stores them trimmed
+fn render_spans(spans: &[TextSpan]) -> String {
+    spans
+        .iter()
+        .filter(|span| !span.text.trim().is_empty())
+        .map(|span| {
+            let text = escape(span.text.trim());
+            match span.style {
+                TextStyle::Normal => text,
+                TextStyle::Bold => format!("<strong>{}</strong>", text),
+                TextStyle::Italic => format!("<em>{}</em>", text),
+                TextStyle::BoldItalic => format!("<strong><em>{}</em></strong>", text),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Chord line as a one-row table, one cell per bar; repeat barlines are cell classes, placed
+/// where the line's measures start and close repeats
+fn render_grid(items: &[ChordItem], measures: &[Measure]) -> String {
+    let mut cells = Vec::new();
+    let mut prefix = String::new();
+    push_cells(items, None, &mut cells, &mut prefix);
+    for (cell, measure) in cells.iter_mut().zip(measures) {
+        cell.repeat_start = measure.repeat_start;
+        cell.repeat_end = measure.repeat_end.is_some();
+    }
+
+    let mut html = String::from("<table class=\"grid\"><tr>");
+    for cell in cells {
+        let classes: Vec<&str> = [
+            ("bar", true),
+            ("repeat-start", cell.repeat_start),
+            ("repeat-end", cell.repeat_end),
+        ]
+        .into_iter()
+        .filter_map(|(class, set)| set.then_some(class))
+        .collect();
+        let times = cell
+            .times
+            .filter(|_| cell.repeat_end)
+            .map(|times| format!(" data-times=\"{}\"", times))
+            .unwrap_or_default();
+        let _ = write!(html, "<td class=\"{}\"{}>{}</td>", classes.join(" "), times, cell.content);
+    }
+    html.push_str("</tr></table>\n");
+    html
+}
+
+/// Cells of the bars in `items`, each with the written times of the repeat it is in
+fn push_cells(items: &[ChordItem], times: Option<u32>, cells: &mut Vec<GridCell>, prefix: &mut String) {
+    for item in items {
+        match item {
+            ChordItem::Bar(bar) => cells.push(GridCell {
+                content: format!("{}{}", std::mem::take(prefix), render_bar(bar)),
+                times,
+                ..GridCell::default()
+            }),
+            ChordItem::RepeatBar => cells.push(GridCell {
+                content: format!("{}<span class=\"repeat-bar\">%</span>", std::mem::take(prefix)),
+                times,
+                ..GridCell::default()
+            }),
+            ChordItem::Group(group) => push_cells(&group.items, group.times, cells, prefix),
+            ChordItem::Ending(number) => {
+                let _ = write!(prefix, "<span class=\"ending\">{}.</span> ", number);
+            }
+            ChordItem::Annotation(text) => {
+                let _ = write!(prefix, "<span class=\"annotation\">{}</span> ", escape(text));
+            }
+        }
+    }
+}
+
+/// Beat slots of a bar; push, accent and ghost chords are marked with classes
+fn render_bar(bar: &Bar) -> String {
+    let beats = bar
+        .beats
+        .iter()
+        .map(|beat| match beat {
+            Beat::Chord(hit) => {
+                let classes: Vec<&str> = [("chord", true), ("push", hit.push), ("accent", hit.accent), ("ghost", hit.ghost)]
+                    .into_iter()
+                    .filter_map(|(class, set)| set.then_some(class))
+                    .collect();
+                format!("<span class=\"{}\">{}</span>", classes.join(" "), escape(&hit.chord.to_string()))
+            }
+            Beat::NoChord => "<span class=\"no-chord\">N.C.</span>".to_string(),
+            Beat::Empty => "<span class=\"empty\">/</span>".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    if bar.fermata {
+        format!("{} <span class=\"fermata\">𝄐</span>", beats)
+    } else {
+        beats
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests;
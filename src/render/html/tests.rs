@@ -0,0 +1,50 @@
+use super::*;
+use crate::parser::parse_chart;
+
+fn fragment() -> HtmlGenerator {
+    HtmlGenerator::new(HtmlConfig {
+        standalone: false,
+        ..HtmlConfig::default()
+    })
+}
+
+#[test]
+fn test_render_headers_and_styled_text() {
+    let chart = parse_chart("=== <>My Song\n= <Play *soft* >**loud** & soft\n").unwrap();
+    let html = fragment().render(&chart);
+
+    assert_eq!(
+        html,
+        "<article class=\"chart\">\n\
+         <h1 class=\"row\"><span class=\"left\"></span><span class=\"center\">My Song</span><span class=\"right\"></span></h1>\n\
+         <h3 class=\"row\"><span class=\"left\">Play <em>soft</em></span><span class=\"center\"></span><span class=\"right\"><strong>loud</strong> &amp; soft</span></h3>\n\
+         </article>\n"
+    );
+}
+
+#[test]
+fn test_render_chord_grid_table() {
+    let chart = parse_chart("<>Am_, % (F 1. E 2. G?) 2x\n").unwrap();
+    let html = fragment().render(&chart);
+
+    assert!(html.contains(
+        "<table class=\"grid\"><tr>\
+         <td class=\"bar\"><span class=\"chord accent\">Am</span> <span class=\"empty\">/</span></td>\
+         <td class=\"bar\"><span class=\"repeat-bar\">%</span></td>\
+         <td class=\"bar repeat-start\"><span class=\"chord\">F</span></td>\
+         <td class=\"bar repeat-end\" data-times=\"2\"><span class=\"ending\">1.</span> <span class=\"chord\">E</span></td>\
+         <td class=\"bar\"><span class=\"ending\">2.</span> <span class=\"chord ghost\">G</span></td>\
+         </tr></table>"
+    ));
+}
+
+#[test]
+fn test_standalone_document_links_stylesheet() {
+    let chart = parse_chart("=== <>Tom & Jerry\nC\n").unwrap();
+    let html = HtmlGenerator::with_defaults().render(&chart);
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Tom &amp; Jerry</title>"));
+    assert!(html.contains("<link rel=\"stylesheet\" href=\"chord-script.css\">"));
+    assert!(STYLESHEET.contains(".chart .grid"));
+}
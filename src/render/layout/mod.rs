@@ -0,0 +1,583 @@
+// Backend-independent layout
+//
+// The layout pass turns a chart into positioned boxes on fixed-size pages: one box per line,
+// holding its columns of styled text, or the cells, barlines and voltas of a chord grid. Output
+// backends only paint the boxes, so every paged format places text the same way and layout can
+// be tested alone.
+
+use super::{ChordSymbols, FontStyle, SvgConfig};
+use crate::model::{Beat, Chart, ChordItem, Line, LineLevel, Page, TextSpan, TextStyle, TimeSignature};
+use crate::parser::{self, cst::LineSource};
+use std::ops::Range;
+
+mod rhythm;
+mod symbol;
+
+pub use rhythm::{BeamBox, SlashBox, TieBox};
+pub use symbol::SymbolRun;
+pub(crate) use symbol::{SUPERSCRIPT_RISE, SUPERSCRIPT_SCALE};
+
+/// Average glyph advance as a fraction of the font size, used to estimate text widths
+const CHAR_WIDTH: f64 = 0.6;
+
+/// Capital letter height as a fraction of the font size, used to center chords in bars
+const CAP_HEIGHT: f64 = 0.7;
+
+/// Height of a chord grid row, in text line heights; the top of the row holds labels
+const GRID_ROW_LINES: f64 = 2.0;
+
+/// Extra height of chord grid rows with slash notation, in text line heights
+const RHYTHM_LINES: f64 = 1.5;
+
+/// Size of ending, annotation, fermata and repeat count labels relative to the text font
+const LABEL_SCALE: f64 = 0.8;
+
+/// Room a repeat sign takes beside its barline, up to the far side of its dots
+pub(crate) const REPEAT_SIGN_WIDTH: f64 = 6.0;
+
+/// Horizontal alignment of a column on its `x` position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// A run of text in one style
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphRun {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+/// Where a box was written in the chart source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRef {
+    /// One-based source line
+    pub line: usize,
+    /// Byte range in the source
+    pub span: Range<usize>,
+}
+
+/// One column of a text line, aligned on `x`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnBox {
+    pub x: f64,
+    pub align: Align,
+    /// Estimated advance of the text
+    pub width: f64,
+    pub runs: Vec<GlyphRun>,
+    /// Set when laid out from source
+    pub source: Option<SourceRef>,
+}
+
+/// A piece of text in a chord grid: a chord at its beat, a repeated bar, or a label above a bar
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChordCell {
+    pub x: f64,
+    pub baseline: f64,
+    /// Estimated advance of the text
+    pub width: f64,
+    /// Font size; the weight is the text font's
+    pub size: f64,
+    /// The whole text, as read aloud or searched
+    pub text: String,
+    /// The text as typeset, with chord qualities raised
+    pub runs: Vec<SymbolRun>,
+    /// Set when laid out from source
+    pub source: Option<SourceRef>,
+}
+
+/// How a barline is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarlineKind {
+    Single,
+    /// Thick and thin lines with dots to the right
+    RepeatStart,
+    /// Dots to the left of thin and thick lines
+    RepeatEnd,
+    /// A repeat end immediately followed by a repeat start
+    RepeatBoth,
+}
+
+/// A vertical barline of a chord grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarlineBox {
+    pub x: f64,
+    pub top: f64,
+    pub bottom: f64,
+    pub kind: BarlineKind,
+}
+
+/// A volta bracket over the bars of a numbered ending, open at the bottom; its number is a
+/// label cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoltaBox {
+    pub x: f64,
+    pub width: f64,
+    /// Height of the horizontal line
+    pub top: f64,
+    /// Where the hooks end, at the top of the bars
+    pub bottom: f64,
+    /// Whether the bracket has a hook on the right, as when the ending closes a repeat
+    pub closed: bool,
+}
+
+/// A positioned line: the columns of a text line, or the cells, barlines and voltas of a chord
+/// grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineBox {
+    pub level: LineLevel,
+    /// Baseline of a text line, or the bottom of a chord grid, from the top of the page
+    pub baseline: f64,
+    pub font: FontStyle,
+    pub columns: Vec<ColumnBox>,
+    pub cells: Vec<ChordCell>,
+    pub barlines: Vec<BarlineBox>,
+    pub voltas: Vec<VoltaBox>,
+    /// Slash notation under the chords, when enabled and the line spells out rhythm
+    pub slashes: Vec<SlashBox>,
+    pub beams: Vec<BeamBox>,
+    pub ties: Vec<TieBox>,
+    /// Set when laid out from source
+    pub source: Option<SourceRef>,
+}
+
+/// A page of positioned lines, in points from the top-left corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageBox {
+    pub width: f64,
+    pub height: f64,
+    pub lines: Vec<LineBox>,
+}
+
+/// A bar of a chord grid before it is positioned
+#[derive(Default)]
+struct GridBar {
+    /// Typeset text per beat slot; empty slots hold the previous chord
+    beats: Vec<Option<Vec<SymbolRun>>>,
+    /// A repeated bar, drawn as a centered `%`
+    repeat: bool,
+    fermata: bool,
+    /// Endings and annotations written before the bar
+    labels: Vec<(String, Option<SourceRef>)>,
+    source: Option<SourceRef>,
+    repeat_start: bool,
+    repeat_end: bool,
+    /// Repeat count, shown at the repeat end
+    times: Option<u32>,
+    /// Numbered ending the bar belongs to
+    ending: Option<u32>,
+    /// Beat slots of a written bar, for slash notation
+    rhythm: Vec<Beat>,
+}
+
+/// The boxes of a chord grid
+#[derive(Default)]
+struct Grid {
+    cells: Vec<ChordCell>,
+    barlines: Vec<BarlineBox>,
+    voltas: Vec<VoltaBox>,
+    rhythm: rhythm::Rhythm,
+}
+
+/// Lays charts out on pages sized and styled by an `SvgConfig`
+pub struct LayoutEngine<'a> {
+    config: &'a SvgConfig,
+}
+
+impl<'a> LayoutEngine<'a> {
+    /// Create a layout engine for the given configuration
+    pub fn new(config: &'a SvgConfig) -> Self {
+        Self { config }
+    }
+
+    /// Lay out a chart, breaking onto new pages when it overflows the bottom margin
+    pub fn layout(&self, chart: &Chart) -> Vec<PageBox> {
+        self.layout_lines(chart, None, None)
+    }
+
+    /// Lay out a page; its footer (if any) sits on the bottom margin of every resulting page
+    pub fn layout_page(&self, page: &Page) -> Vec<PageBox> {
+        self.layout_lines(&page.chart, None, page.footer.as_ref())
+    }
+
+    /// Parse and lay out chart source, recording where every line, column and cell came from
+    pub fn layout_source(&self, source: &str) -> parser::Result<Vec<PageBox>> {
+        let tree = parser::parse_cst(source)?;
+        let sources = tree.line_sources();
+        Ok(self.layout_lines(&tree.to_chart(), Some((source, &sources)), None))
+    }
+
+    fn layout_lines(
+        &self,
+        chart: &Chart,
+        sources: Option<(&str, &[LineSource])>,
+        footer: Option<&Line>,
+    ) -> Vec<PageBox> {
+        let layout = &self.config.layout;
+        let time = chart.time_signature().unwrap_or_default();
+        let bottom = layout.height - layout.margin_vertical;
+        let footer = footer.map(|footer| self.line_box(footer, bottom - self.line_height(footer), None, time));
+        // Body lines stop one footer line above the bottom margin
+        let limit = footer.as_ref().map_or(bottom, |footer| bottom - footer.font.line_height);
+
+        let mut pages = Vec::new();
+        let mut boxes = Vec::new();
+        let mut y = layout.margin_vertical;
+        for (index, line) in chart.lines.iter().enumerate() {
+            let line_height = self.line_height(line);
+            if y + line_height > limit && !boxes.is_empty() {
+                pages.push(self.page_box(std::mem::take(&mut boxes), footer.as_ref()));
+                y = layout.margin_vertical;
+            }
+            let source = sources.map(|(text, sources)| (text, &sources[index]));
+            boxes.push(self.line_box(line, y, source, time));
+            y += line_height;
+        }
+        pages.push(self.page_box(boxes, footer.as_ref()));
+        pages
+    }
+
+    fn page_box(&self, mut lines: Vec<LineBox>, footer: Option<&LineBox>) -> PageBox {
+        lines.extend(footer.cloned());
+        PageBox {
+            width: self.config.layout.width,
+            height: self.config.layout.height,
+            lines,
+        }
+    }
+
+    /// Vertical space taken by a line: one line height, or every row of a chord grid
+    fn line_height(&self, line: &Line) -> f64 {
+        if line.chords.is_empty() {
+            return self.font_style(line.level).line_height;
+        }
+        let bars = grid_bars(&line.chords, &[], self.config.chord_symbols);
+        let rows = bars.len().div_ceil(self.bars_per_row()).max(1);
+        rows as f64 * self.row_height(self.rhythmic(&bars))
+    }
+
+    /// Position one line below `top`
+    fn line_box(&self, line: &Line, top: f64, source: Option<(&str, &LineSource)>, time: TimeSignature) -> LineBox {
+        let layout = &self.config.layout;
+        let font = self.font_style(line.level).clone();
+        let source_ref = |span: Option<&Range<usize>>| {
+            let (text, span) = (source?.0, span?);
+            Some(SourceRef {
+                line: text[..span.start].matches('\n').count() + 1,
+                span: span.clone(),
+            })
+        };
+
+        let columns = [
+            (&line.left, layout.margin_horizontal, Align::Left),
+            (&line.center, layout.width / 2.0, Align::Center),
+            (&line.right, layout.width - layout.margin_horizontal, Align::Right),
+        ]
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (spans, _, _))| !spans.is_empty())
+        .map(|(index, (spans, x, align))| ColumnBox {
+            x,
+            align,
+            width: text_width(&TextSpan::join(spans), font.size),
+            runs: spans.iter().map(glyph_run).collect(),
+            source: source_ref(source.and_then(|(_, line)| line.columns[index].as_ref())),
+        })
+        .collect();
+
+        let item_sources: Vec<Option<SourceRef>> = (0..line.chords.len())
+            .map(|index| source_ref(source.and_then(|(_, line)| line.items.get(index))))
+            .collect();
+        let grid = self.grid(&grid_bars(&line.chords, &item_sources, self.config.chord_symbols), top, time);
+
+        LineBox {
+            level: line.level,
+            baseline: top + self.line_height(line),
+            font,
+            columns,
+            cells: grid.cells,
+            barlines: grid.barlines,
+            voltas: grid.voltas,
+            slashes: grid.rhythm.slashes,
+            beams: grid.rhythm.beams,
+            ties: grid.rhythm.ties,
+            source: source_ref(source.map(|(_, line)| &line.span)),
+        }
+    }
+
+    /// Place bars in rows of equal-width bars below `top`, so bars line up across rows
+    fn grid(&self, bars: &[GridBar], top: f64, time: TimeSignature) -> Grid {
+        let layout = &self.config.layout;
+        let font = &self.config.text;
+        let per_row = self.bars_per_row();
+        let bar_width = (layout.width - 2.0 * layout.margin_horizontal) / per_row as f64;
+        let padding = CHAR_WIDTH * font.size;
+        let label_size = font.size * LABEL_SCALE;
+
+        let rhythmic = self.rhythmic(bars);
+        let notes = match rhythmic {
+            true => rhythm::notes(&bar_beats(bars), time),
+            false => Vec::new(),
+        };
+        let row_height = self.row_height(rhythmic);
+
+        let mut grid = Grid::default();
+        let mut index = 0;
+        for (row, row_bars) in bars.chunks(per_row).enumerate() {
+            let row_top = top + row as f64 * row_height;
+            let bar_top = row_top + font.size;
+            let bottom = row_top + row_height;
+            // Chords keep the height of a plain row; slash notation goes below them
+            let chord_bottom = row_top + self.row_height(false);
+            let baseline = bar_top + (chord_bottom - bar_top + CAP_HEIGHT * font.size) / 2.0;
+            let mut previous_note = None;
+            let label_baseline = bar_top - (font.size - CAP_HEIGHT * label_size) / 2.0;
+            let label = |x: f64, text: &str, source: &Option<SourceRef>| ChordCell {
+                x,
+                baseline: label_baseline,
+                width: text_width(text, label_size),
+                size: label_size,
+                text: text.to_string(),
+                runs: vec![SymbolRun::plain(text)],
+                source: source.clone(),
+            };
+
+            for (column, bar) in row_bars.iter().enumerate() {
+                let x = layout.margin_horizontal + column as f64 * bar_width;
+                // The previous bar's repeat end shares this barline, unless it closed the last row
+                let previous_end = column > 0 && bars[index - 1].repeat_end;
+                let kind = match (previous_end, bar.repeat_start) {
+                    (true, true) => BarlineKind::RepeatBoth,
+                    (true, false) => BarlineKind::RepeatEnd,
+                    (false, true) => BarlineKind::RepeatStart,
+                    (false, false) => BarlineKind::Single,
+                };
+                grid.barlines.push(BarlineBox { x, top: bar_top, bottom, kind });
+
+                // Consecutive bars of an ending in a row share one bracket
+                if let Some(ending) = bar.ending {
+                    let continues = column > 0 && bars[index - 1].ending == Some(ending);
+                    match grid.voltas.last_mut() {
+                        Some(volta) if continues => volta.width += bar_width,
+                        _ => grid.voltas.push(VoltaBox {
+                            x,
+                            width: bar_width,
+                            top: row_top + 1.0,
+                            bottom: bar_top,
+                            closed: false,
+                        }),
+                    }
+                    if let Some(volta) = grid.voltas.last_mut() {
+                        volta.closed = bar.repeat_end;
+                    }
+                }
+
+                let start = x + padding + if bar.repeat_start { REPEAT_SIGN_WIDTH } else { 0.0 };
+                let end = x + bar_width - padding - if bar.repeat_end { REPEAT_SIGN_WIDTH } else { 0.0 };
+
+                let mut label_x = x + padding;
+                for (text, source) in &bar.labels {
+                    let cell = label(label_x, text, source);
+                    label_x += cell.width + CHAR_WIDTH * label_size;
+                    grid.cells.push(cell);
+                }
+                // Right-aligned over the end of the bar: the fermata, then the repeat count
+                let mut right = x + bar_width - padding;
+                let count = bar.times.map(|times| format!("×{}", times));
+                for text in [count.as_deref(), bar.fermata.then_some("𝄐")].into_iter().flatten() {
+                    let mut cell = label(right, text, &bar.source);
+                    cell.x -= cell.width;
+                    right = cell.x - CHAR_WIDTH * label_size;
+                    grid.cells.push(cell);
+                }
+
+                // Beats share the room between repeat signs; a repeated bar sits in the middle
+                let slot = (end - start) / bar.beats.len().max(1) as f64;
+                for (beat, runs) in bar.beats.iter().enumerate() {
+                    let Some(runs) = runs else { continue };
+                    let width = runs_width(runs, font.size);
+                    let x = match bar.repeat {
+                        true => x + (bar_width - width) / 2.0,
+                        false => start + beat as f64 * slot,
+                    };
+                    grid.cells.push(ChordCell {
+                        x,
+                        baseline,
+                        width,
+                        size: font.size,
+                        text: runs.iter().map(|run| run.text.as_str()).collect(),
+                        runs: runs.clone(),
+                        source: bar.source.clone(),
+                    });
+                }
+
+                if let Some(notes) = notes.get(index) {
+                    let staff = rhythm::Staff {
+                        start,
+                        end,
+                        top: chord_bottom,
+                        row_start: layout.margin_horizontal,
+                        size: font.size,
+                    };
+                    rhythm::place(notes, time, &staff, &mut previous_note, &mut grid.rhythm);
+                }
+                index += 1;
+            }
+
+            let last = &bars[index - 1];
+            grid.barlines.push(BarlineBox {
+                x: layout.margin_horizontal + row_bars.len() as f64 * bar_width,
+                top: bar_top,
+                bottom,
+                kind: if last.repeat_end { BarlineKind::RepeatEnd } else { BarlineKind::Single },
+            });
+        }
+        grid
+    }
+
+    fn bars_per_row(&self) -> usize {
+        self.config.bars_per_row.max(1)
+    }
+
+    /// Height of a chord grid row, taller with slash notation
+    fn row_height(&self, rhythmic: bool) -> f64 {
+        let extra = if rhythmic { RHYTHM_LINES } else { 0.0 };
+        self.config.text.line_height * (GRID_ROW_LINES + extra)
+    }
+
+    /// Whether a chord grid gets slash notation: enabled, and the line spells out rhythm
+    fn rhythmic(&self, bars: &[GridBar]) -> bool {
+        self.config.slash_notation && rhythm::specifies_rhythm(&bar_beats(bars))
+    }
+
+    fn font_style(&self, level: LineLevel) -> &FontStyle {
+        match level {
+            LineLevel::Header1 => &self.config.header1,
+            LineLevel::Header2 => &self.config.header2,
+            LineLevel::Header3 => &self.config.header3,
+            LineLevel::Text | LineLevel::Chords => &self.config.text,
+        }
+    }
+}
+
+/// Flatten chord items into bars; `sources` runs parallel to `items`, and bars inside a repeat
+/// group map to the whole group
+fn grid_bars(items: &[ChordItem], sources: &[Option<SourceRef>], style: ChordSymbols) -> Vec<GridBar> {
+    let mut bars = Vec::new();
+    let mut labels = Vec::new();
+    let source = |index: usize| sources.get(index).cloned().flatten();
+    push_bars(items, &source, style, &mut bars, &mut labels, &mut None);
+
+    // Labels after the last bar still belong to the line
+    match bars.last_mut() {
+        Some(bar) => bar.labels.extend(labels),
+        None if !labels.is_empty() => bars.push(GridBar {
+            labels,
+            ..GridBar::default()
+        }),
+        None => {}
+    }
+    bars
+}
+
+fn push_bars(
+    items: &[ChordItem],
+    source: &dyn Fn(usize) -> Option<SourceRef>,
+    style: ChordSymbols,
+    bars: &mut Vec<GridBar>,
+    labels: &mut Vec<(String, Option<SourceRef>)>,
+    ending: &mut Option<u32>,
+) {
+    for (index, item) in items.iter().enumerate() {
+        match item {
+            ChordItem::Bar(bar) => bars.push(GridBar {
+                beats: bar.beats.iter().map(|beat| beat_runs(beat, style)).collect(),
+                fermata: bar.fermata,
+                labels: std::mem::take(labels),
+                source: source(index),
+                ending: *ending,
+                rhythm: bar.beats.clone(),
+                ..GridBar::default()
+            }),
+            ChordItem::RepeatBar => bars.push(GridBar {
+                beats: vec![Some(vec![SymbolRun::plain("%")])],
+                repeat: true,
+                labels: std::mem::take(labels),
+                source: source(index),
+                ending: *ending,
+                ..GridBar::default()
+            }),
+            ChordItem::Group(group) => {
+                let first = bars.len();
+                let group_source = source(index);
+                push_bars(&group.items, &|_| group_source.clone(), style, bars, labels, &mut None);
+                close_repeat(&mut bars[first..], group.times);
+            }
+            ChordItem::Ending(number) => {
+                *ending = Some(*number);
+                labels.push((format!("{}.", number), source(index)));
+            }
+            ChordItem::Annotation(text) => labels.push((text.clone(), source(index))),
+        }
+    }
+}
+
+fn bar_beats(bars: &[GridBar]) -> Vec<&[Beat]> {
+    bars.iter().map(|bar| bar.rhythm.as_slice()).collect()
+}
+
+/// Mark the repeat signs of a group's bars. With two or more endings, the last ending is played
+/// after the repeat, so the repeat closes just before it.
+fn close_repeat(bars: &mut [GridBar], times: Option<u32>) {
+    let Some(first) = bars.first_mut() else { return };
+    first.repeat_start = true;
+
+    let last_ending = bars.last().and_then(|bar| bar.ending);
+    let earlier_ending = bars.iter().any(|bar| bar.ending.is_some() && bar.ending != last_ending);
+    let end = match last_ending {
+        Some(number) if earlier_ending => bars.iter().position(|bar| bar.ending == Some(number)).unwrap_or(bars.len()),
+        _ => bars.len(),
+    };
+    if let Some(bar) = end.checked_sub(1).and_then(|end| bars.get_mut(end)) {
+        bar.repeat_end = true;
+        bar.times = times;
+    }
+}
+
+/// Typeset chord for a beat slot; ghost chords are parenthesized
+fn beat_runs(beat: &Beat, style: ChordSymbols) -> Option<Vec<SymbolRun>> {
+    match beat {
+        Beat::Chord(hit) if hit.ghost => {
+            let mut runs = vec![SymbolRun::plain("(")];
+            runs.extend(symbol::typeset(&hit.chord, style));
+            runs.push(SymbolRun::plain(")"));
+            Some(symbol::merge(runs))
+        }
+        Beat::Chord(hit) => Some(symbol::typeset(&hit.chord, style)),
+        Beat::NoChord => Some(vec![SymbolRun::plain("N.C.")]),
+        Beat::Empty => None,
+    }
+}
+
+/// Estimated advance of typeset runs, with raised runs at their smaller size
+fn runs_width(runs: &[SymbolRun], size: f64) -> f64 {
+    runs.iter()
+        .map(|run| text_width(&run.text, if run.raised { size * SUPERSCRIPT_SCALE } else { size }))
+        .sum()
+}
+
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().count() as f64 * CHAR_WIDTH * size
+}
+
+fn glyph_run(span: &TextSpan) -> GlyphRun {
+    GlyphRun {
+        text: span.text.clone(),
+        style: span.style,
+    }
+}
+
+#[cfg(test)]
+mod tests;
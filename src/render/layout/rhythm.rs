@@ -0,0 +1,318 @@
+// Rhythmic slash notation
+//
+// Chord lines that spell out rhythm (split bars, pushes or stabs) can be shown with slash
+// noteheads under the chords. Beat slots share the bar as in the MIDI and MusicXML exports;
+// plain bars get one stemless slash per beat, and pushes land an eighth early, tied over the
+// beat.
+
+use crate::model::{Beat, TimeSignature};
+
+/// Duration units per quarter note, as in the MusicXML export
+const DIVISIONS: i32 = 12;
+
+/// How early a pushed chord lands
+const EIGHTH: i32 = DIVISIONS / 2;
+
+/// Notehead size relative to the text font
+const NOTEHEAD_WIDTH: f64 = 0.8;
+const NOTEHEAD_HEIGHT: f64 = 0.6;
+
+/// Notehead center below the top of the staff, relative to the text font
+const NOTE_LINE: f64 = 0.8;
+
+/// Stem length below the notehead center, relative to the text font
+const STEM_LENGTH: f64 = 1.2;
+
+/// A slash notehead centered on (`x`, `y`), with its stem, flags, dot and accent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlashBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Half and whole notes are drawn hollow
+    pub hollow: bool,
+    /// End of the stem hanging from the notehead's left edge, if it has one
+    pub stem: Option<f64>,
+    /// Flags on an unbeamed stem
+    pub flags: usize,
+    pub dotted: bool,
+    /// Accent mark above a stab
+    pub accent: bool,
+}
+
+/// Beams joining the stem ends from `x1` to `x2`, the first on `y` and the rest stacked above
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeamBox {
+    pub x1: f64,
+    pub x2: f64,
+    pub y: f64,
+    pub count: usize,
+}
+
+/// A tie from `x1` to `x2`, its ends on `y` and bowed upwards
+#[derive(Debug, Clone, PartialEq)]
+pub struct TieBox {
+    pub x1: f64,
+    pub x2: f64,
+    pub y: f64,
+}
+
+/// A note of a bar, timed in `DIVISIONS` per quarter from the start of the bar
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RhythmNote {
+    /// Negative for a push into the first bar of a line
+    start: i32,
+    duration: i32,
+    accent: bool,
+    /// Tied from the previous note of the line
+    tied: bool,
+    /// A plain beat slash of a bar without rhythm
+    stemless: bool,
+}
+
+/// How a duration is written
+#[derive(Clone, Copy)]
+struct NoteValue {
+    hollow: bool,
+    stem: bool,
+    beams: usize,
+    dotted: bool,
+}
+
+/// Everything drawn for the rhythm of a line
+#[derive(Default)]
+pub(super) struct Rhythm {
+    pub slashes: Vec<SlashBox>,
+    pub beams: Vec<BeamBox>,
+    pub ties: Vec<TieBox>,
+}
+
+/// Where a bar's notes go: the room between its repeat signs, below the chords
+pub(super) struct Staff {
+    pub start: f64,
+    pub end: f64,
+    pub top: f64,
+    /// Left edge of the row, where ties from the previous row start
+    pub row_start: f64,
+    /// Text font size the notation scales with
+    pub size: f64,
+}
+
+/// Whether bars spell out rhythm: more than one beat slot, a push or a stab
+pub(super) fn specifies_rhythm(bars: &[&[Beat]]) -> bool {
+    bars.iter().any(|beats| {
+        beats.len() > 1 || beats.iter().any(|beat| matches!(beat, Beat::Chord(hit) if hit.push || hit.accent))
+    })
+}
+
+/// Notes for each bar of a line
+pub(super) fn notes(bars: &[&[Beat]], time: TimeSignature) -> Vec<Vec<RhythmNote>> {
+    let measure = measure_duration(time);
+    let mut notes: Vec<Vec<RhythmNote>> = Vec::new();
+    for beats in bars {
+        let mut bar = Vec::new();
+        if let [Beat::Chord(hit)] = beats {
+            if !hit.push && !hit.accent {
+                let beat = measure / time.beats as i32;
+                bar = (0..time.beats as i32)
+                    .map(|index| note(index * beat, beat, false, false, true))
+                    .collect();
+                notes.push(bar);
+                continue;
+            }
+        }
+
+        let slot = measure / beats.len().max(1) as i32;
+        for (index, beat) in beats.iter().enumerate() {
+            let start = slot * index as i32;
+            let duration = if index + 1 == beats.len() { measure - start } else { slot };
+            match beat {
+                Beat::Chord(hit) if hit.push => {
+                    // A push at the start of a bar is played at the end of the one before
+                    match notes.last_mut().filter(|previous| bar.is_empty() && !previous.is_empty()) {
+                        Some(previous) => push_anticipation(previous, measure, hit.accent),
+                        None => push_anticipation(&mut bar, start, hit.accent),
+                    }
+                    bar.push(note(start, duration, false, true, false));
+                }
+                Beat::Chord(hit) => bar.push(note(start, duration, hit.accent, false, false)),
+                // A held chord lengthens the previous note when the sum is a single note value
+                Beat::Empty => match bar.last_mut() {
+                    Some(previous)
+                        if !previous.stemless
+                            && previous.start + previous.duration == start
+                            && note_value(previous.duration + duration).is_some() =>
+                    {
+                        previous.duration += duration
+                    }
+                    _ => bar.push(note(start, duration, false, true, false)),
+                },
+                // Rests are left as gaps
+                Beat::NoChord => {}
+            }
+        }
+        notes.push(bar);
+    }
+
+    // Pushes can leave notes no single value lasts, as a half note less an eighth
+    for bar in &mut notes {
+        *bar = bar.drain(..).flat_map(split_tied).collect();
+    }
+    notes
+}
+
+/// Position a bar's notes on its staff. `previous` is the last notehead of the row, where a
+/// tie into this bar starts.
+pub(super) fn place(
+    notes: &[RhythmNote],
+    time: TimeSignature,
+    staff: &Staff,
+    previous: &mut Option<f64>,
+    rhythm: &mut Rhythm,
+) {
+    let measure = measure_duration(time) as f64;
+    let (width, height) = (NOTEHEAD_WIDTH * staff.size, NOTEHEAD_HEIGHT * staff.size);
+    let y = staff.top + NOTE_LINE * staff.size;
+    let stem_end = y + STEM_LENGTH * staff.size;
+    let quarter = NoteValue {
+        hollow: false,
+        stem: true,
+        beams: 0,
+        dotted: false,
+    };
+
+    // Runs of neighbouring notes with the same number of beams share them; a lone note gets
+    // flags instead. Group entries are (slash index, stem x, beams).
+    let mut group: Vec<(usize, f64, usize)> = Vec::new();
+    let mut group_end = None;
+    let flush = |group: &mut Vec<(usize, f64, usize)>, rhythm: &mut Rhythm| {
+        match *group.as_slice() {
+            [] => {}
+            [(index, _, count)] => rhythm.slashes[index].flags = count,
+            [(_, x1, count), .., (_, x2, _)] => rhythm.beams.push(BeamBox {
+                x1,
+                x2,
+                y: stem_end,
+                count,
+            }),
+        }
+        group.clear();
+    };
+
+    for note in notes {
+        let x = staff.start + note.start as f64 / measure * (staff.end - staff.start) + width / 2.0;
+        let value = match note.stemless {
+            true => NoteValue {
+                stem: false,
+                ..quarter
+            },
+            false => note_value(note.duration).unwrap_or(quarter),
+        };
+
+        if note.tied {
+            let x1 = previous.map_or(staff.row_start, |previous| previous + width / 4.0);
+            rhythm.ties.push(TieBox {
+                x1,
+                x2: x - width / 4.0,
+                y: y - height / 2.0 - 1.0,
+            });
+        }
+        *previous = Some(x);
+
+        let stem_x = x - width / 2.0;
+        let joins = group_end == Some(note.start) && group.last().is_some_and(|&(_, _, beams)| beams == value.beams);
+        if !joins {
+            flush(&mut group, rhythm);
+        }
+        rhythm.slashes.push(SlashBox {
+            x,
+            y,
+            width,
+            height,
+            hollow: value.hollow,
+            stem: value.stem.then_some(stem_end),
+            flags: 0,
+            dotted: value.dotted,
+            accent: note.accent,
+        });
+        if value.beams > 0 {
+            group.push((rhythm.slashes.len() - 1, stem_x, value.beams));
+            group_end = Some(note.start + note.duration);
+        } else {
+            flush(&mut group, rhythm);
+            group_end = None;
+        }
+    }
+    flush(&mut group, rhythm);
+}
+
+fn note(start: i32, duration: i32, accent: bool, tied: bool, stemless: bool) -> RhythmNote {
+    RhythmNote {
+        start,
+        duration,
+        accent,
+        tied,
+        stemless,
+    }
+}
+
+/// Add an eighth note ending at `at`, shortening the note it cuts into
+fn push_anticipation(bar: &mut Vec<RhythmNote>, at: i32, accent: bool) {
+    let start = at - EIGHTH;
+    if let Some(previous) = bar.last_mut() {
+        if previous.start < start && previous.start + previous.duration > start {
+            previous.duration = start - previous.start;
+        }
+    }
+    bar.push(note(start, EIGHTH, accent, false, false));
+}
+
+/// Split a note into tied notes of written values, longest first; durations that do not add
+/// up from them (tuplets) stay whole
+fn split_tied(note: RhythmNote) -> Vec<RhythmNote> {
+    const VALUES: [i32; 8] = [48, 36, 24, 18, 12, 9, 6, 3];
+    if note.stemless || note_value(note.duration).is_some() || note.duration % 3 != 0 {
+        return vec![note];
+    }
+    let mut pieces = Vec::new();
+    let (mut start, end) = (note.start, note.start + note.duration);
+    while start < end {
+        let Some(&duration) = VALUES.iter().find(|&&value| value <= end - start) else { break };
+        let first = pieces.is_empty();
+        pieces.push(RhythmNote {
+            start,
+            duration,
+            accent: note.accent && first,
+            tied: note.tied || !first,
+            stemless: false,
+        });
+        start += duration;
+    }
+    pieces
+}
+
+fn measure_duration(time: TimeSignature) -> i32 {
+    (time.beats * DIVISIONS as u32 * 4 / time.beat_type.max(1)) as i32
+}
+
+/// The single note that lasts `duration`, if there is one
+fn note_value(duration: i32) -> Option<NoteValue> {
+    let (hollow, stem, beams, dotted) = match duration {
+        48 => (true, false, 0, false),
+        36 => (true, true, 0, true),
+        24 => (true, true, 0, false),
+        18 => (false, true, 0, true),
+        12 => (false, true, 0, false),
+        9 => (false, true, 1, true),
+        6 => (false, true, 1, false),
+        3 => (false, true, 2, false),
+        _ => return None,
+    };
+    Some(NoteValue {
+        hollow,
+        stem,
+        beams,
+        dotted,
+    })
+}
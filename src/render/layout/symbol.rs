@@ -0,0 +1,139 @@
+// Chord symbol typesetting
+//
+// A chord is set as runs: the root with a real sharp or flat and any minor sign at full size,
+// the rest of the quality smaller and raised, and a slash bass back on the baseline. Qualities
+// are respelled in the configured `ChordSymbols` style, whatever shorthand the source used.
+
+use super::ChordSymbols;
+use crate::model::{Accidental, Chord, Note};
+
+/// Size of raised quality text relative to the chord's font size
+pub(crate) const SUPERSCRIPT_SCALE: f64 = 0.7;
+
+/// How far raised quality text sits above the baseline, as a fraction of the font size
+pub(crate) const SUPERSCRIPT_RISE: f64 = 0.35;
+
+/// A run of a chord grid cell, on the baseline or raised as a superscript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolRun {
+    pub text: String,
+    pub raised: bool,
+}
+
+impl SymbolRun {
+    pub(super) fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            raised: false,
+        }
+    }
+}
+
+/// Triad written at the start of a quality
+enum Triad {
+    Major,
+    Minor,
+    Diminished,
+    HalfDiminished,
+    Augmented,
+}
+
+/// Runs for a chord symbol in the given style
+pub(super) fn typeset(chord: &Chord, style: ChordSymbols) -> Vec<SymbolRun> {
+    let mut runs = vec![SymbolRun::plain(note_text(chord.root))];
+    let (triad, rest) = split_triad(&chord.quality);
+    let extensions = respell(rest, style);
+
+    let (minor, raised) = match (style, triad) {
+        (_, Triad::Major) => ("", extensions),
+        (ChordSymbols::Spelled, Triad::Minor) => ("m", extensions),
+        (ChordSymbols::Spelled, Triad::Diminished) => ("", format!("dim{}", extensions)),
+        (ChordSymbols::Spelled, Triad::HalfDiminished) => ("m", format!("7♭5{}", extensions)),
+        (ChordSymbols::Spelled, Triad::Augmented) => ("", format!("aug{}", extensions)),
+        (ChordSymbols::Jazz, Triad::Minor) => ("−", extensions),
+        (ChordSymbols::Jazz, Triad::Diminished) => ("", format!("°{}", extensions)),
+        (ChordSymbols::Jazz, Triad::HalfDiminished) => ("", format!("ø7{}", extensions)),
+        (ChordSymbols::Jazz, Triad::Augmented) => ("", format!("+{}", extensions)),
+    };
+    runs[0].text.push_str(minor);
+    if !raised.is_empty() {
+        runs.push(SymbolRun { text: raised, raised: true });
+    }
+    if let Some(bass) = chord.bass {
+        runs.push(SymbolRun::plain(format!("/{}", note_text(bass))));
+    }
+    runs
+}
+
+/// Join neighbouring runs that sit on the same line, so plain text stays one run
+pub(super) fn merge(runs: Vec<SymbolRun>) -> Vec<SymbolRun> {
+    let mut merged: Vec<SymbolRun> = Vec::new();
+    for run in runs {
+        match merged.last_mut() {
+            Some(last) if last.raised == run.raised => last.text.push_str(&run.text),
+            _ => merged.push(run),
+        }
+    }
+    merged
+}
+
+/// A note letter with its accidental as a music glyph
+fn note_text(note: Note) -> String {
+    let accidental = match note.accidental {
+        Accidental::DoubleFlat => "𝄫",
+        Accidental::Flat => "♭",
+        Accidental::Natural => "",
+        Accidental::Sharp => "♯",
+        Accidental::DoubleSharp => "𝄪",
+    };
+    format!("{}{}", note.name.as_char(), accidental)
+}
+
+/// Recognise the triad prefixes `Chord::intervals` reads, returning the remaining extensions.
+/// A minor seventh with a flat five (`m7b5`) counts as half-diminished.
+fn split_triad(quality: &str) -> (Triad, &str) {
+    if let Some(rest) = quality.strip_prefix('ø') {
+        return (Triad::HalfDiminished, rest.strip_prefix('7').unwrap_or(rest));
+    }
+    if let Some(rest) = ["dim", "°", "o"].iter().find_map(|prefix| quality.strip_prefix(prefix)) {
+        return (Triad::Diminished, rest);
+    }
+    if let Some(rest) = ["aug", "+"].iter().find_map(|prefix| quality.strip_prefix(prefix)) {
+        return (Triad::Augmented, rest);
+    }
+    if quality.starts_with("maj") {
+        return (Triad::Major, quality);
+    }
+    if let Some(rest) = ["min", "m", "-"].iter().find_map(|prefix| quality.strip_prefix(prefix)) {
+        return match ["7b5", "7♭5", "7-5"].iter().find_map(|prefix| rest.strip_prefix(prefix)) {
+            Some(rest) => (Triad::HalfDiminished, rest),
+            None => (Triad::Minor, rest),
+        };
+    }
+    (Triad::Major, quality)
+}
+
+/// Respell major sevenths in the style and alterations with sharp and flat glyphs
+fn respell(extensions: &str, style: ChordSymbols) -> String {
+    let major = match style {
+        ChordSymbols::Spelled => "maj",
+        ChordSymbols::Jazz => "Δ",
+    };
+    let mut text = String::new();
+    let mut rest = extensions;
+    while let Some(c) = rest.chars().next() {
+        if let Some(r) = ["maj", "Maj", "M", "Δ", "△", "^"].iter().find_map(|p| rest.strip_prefix(p)) {
+            text.push_str(major);
+            rest = r;
+            continue;
+        }
+        let before_digit = rest[c.len_utf8()..].starts_with(|c: char| c.is_ascii_digit());
+        match c {
+            'b' | '-' if before_digit => text.push('♭'),
+            '#' | '+' if before_digit => text.push('♯'),
+            c => text.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    text
+}
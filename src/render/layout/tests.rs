@@ -0,0 +1,162 @@
+use super::*;
+use crate::parser::parse_chart;
+
+#[test]
+fn test_columns_and_baselines() {
+    let config = SvgConfig::default();
+    let chart = parse_chart("=== <>Song\n- <left <>*mid* >right\n").unwrap();
+    let pages = LayoutEngine::new(&config).layout(&chart);
+
+    assert_eq!(pages.len(), 1);
+    let [title, text] = pages[0].lines.as_slice() else {
+        panic!("expected two lines");
+    };
+    // Baselines advance by each level's line height from the top margin
+    assert_eq!(title.baseline, 28.0 + 24.0);
+    assert_eq!(text.baseline, 28.0 + 24.0 + 14.0);
+    assert_eq!(title.font, config.header1);
+
+    let placement: Vec<(f64, Align)> = text.columns.iter().map(|column| (column.x, column.align)).collect();
+    assert_eq!(placement, [(28.0, Align::Left), (297.5, Align::Center), (567.0, Align::Right)]);
+    assert_eq!(text.columns[1].runs[0].style, TextStyle::Italic);
+}
+
+#[test]
+fn test_chord_grid_aligns_bars() {
+    let mut config = SvgConfig::default();
+    let chart = parse_chart("C_G F % Dm E\n").unwrap();
+    let pages = LayoutEngine::new(&config).layout(&chart);
+    let grid = &pages[0].lines[0];
+
+    let cells: Vec<(&str, f64, f64)> = grid.cells.iter().map(|cell| (cell.text.as_str(), cell.x, cell.baseline)).collect();
+    // Four 134.75pt bars per row: beats split the bar, `%` is centered, E wraps to the next row
+    assert_eq!(
+        cells,
+        [
+            ("C", 34.0, 50.5),
+            ("G", 95.375, 50.5),
+            ("F", 168.75, 50.5),
+            ("%", 361.875, 50.5),
+            ("Dm", 438.25, 50.5),
+            ("E", 34.0, 78.5),
+        ]
+    );
+    let barlines: Vec<f64> = grid.barlines.iter().map(|barline| barline.x).collect();
+    assert_eq!(barlines, [28.0, 162.75, 297.5, 432.25, 567.0, 28.0, 162.75]);
+    assert_eq!(grid.baseline, 28.0 + 2.0 * 28.0);
+
+    config.bars_per_row = 2;
+    let pages = LayoutEngine::new(&config).layout(&chart);
+    assert_eq!(pages[0].lines[0].baseline, 28.0 + 3.0 * 28.0);
+}
+
+#[test]
+fn test_grid_labels_sit_above_bars() {
+    let config = SvgConfig::default();
+    let chart = parse_chart("(C 1. F 2. G,, fermata) \"fine\"\n").unwrap();
+    let pages = LayoutEngine::new(&config).layout(&chart);
+    let grid = &pages[0].lines[0];
+
+    let labels: Vec<&str> = grid
+        .cells
+        .iter()
+        .filter(|cell| cell.baseline < grid.barlines[0].top)
+        .map(|cell| cell.text.as_str())
+        .collect();
+    assert_eq!(labels, ["1.", "2.", "fine", "𝄐"]);
+}
+
+#[test]
+fn test_page_breaks_repeat_footer() {
+    let config = SvgConfig::default();
+    let lines = vec![Line::plain_text(LineLevel::Text, "line", "", ""); 100];
+    let page = Page {
+        chart: Chart::new(lines),
+        footer: Some(Line::plain_text(LineLevel::Text, "Setlist", "", "1 / 1")),
+    };
+    let pages = LayoutEngine::new(&config).layout_page(&page);
+
+    // 786pt between the margins holds 55 lines of 14pt above the footer line
+    let body: Vec<usize> = pages.iter().map(|page| page.lines.len() - 1).collect();
+    assert_eq!(body, [55, 45]);
+    for page in &pages {
+        let footer = page.lines.last().unwrap();
+        assert_eq!(footer.baseline, 814.0);
+        assert!(page.lines.iter().rev().skip(1).all(|line| line.baseline <= 800.0));
+    }
+}
+
+#[test]
+fn test_repeats_and_voltas() {
+    let config = SvgConfig::default();
+    let chart = parse_chart("(C 1. F 2. G) 3x\n").unwrap();
+    let pages = LayoutEngine::new(&config).layout(&chart);
+    let grid = &pages[0].lines[0];
+
+    // The repeat closes after the first ending; the second is played once
+    let kinds: Vec<BarlineKind> = grid.barlines.iter().map(|barline| barline.kind).collect();
+    assert_eq!(
+        kinds,
+        [BarlineKind::RepeatStart, BarlineKind::Single, BarlineKind::RepeatEnd, BarlineKind::Single]
+    );
+    let voltas: Vec<(f64, f64, bool)> = grid.voltas.iter().map(|volta| (volta.x, volta.width, volta.closed)).collect();
+    assert_eq!(voltas, [(162.75, 134.75, true), (297.5, 134.75, false)]);
+
+    let count = grid.cells.iter().find(|cell| cell.text == "×3").unwrap();
+    assert_eq!(count.x + count.width, 297.5 - 6.0);
+    // Chords keep clear of the repeat sign dots
+    assert_eq!(grid.cells.iter().find(|cell| cell.text == "C").unwrap().x, 28.0 + 6.0 + REPEAT_SIGN_WIDTH);
+}
+
+#[test]
+fn test_chord_symbols_raise_qualities() {
+    let mut config = SvgConfig::default();
+    let chart = parse_chart("F#m7b5 Cmaj7/Eb Am G?\n").unwrap();
+    let runs = |config: &SvgConfig| -> Vec<Vec<(String, bool)>> {
+        let pages = LayoutEngine::new(config).layout(&chart);
+        pages[0].lines[0]
+            .cells
+            .iter()
+            .map(|cell| cell.runs.iter().map(|run| (run.text.clone(), run.raised)).collect())
+            .collect()
+    };
+    let run = |text: &str, raised: bool| (text.to_string(), raised);
+
+    assert_eq!(
+        runs(&config),
+        [
+            vec![run("F♯m", false), run("7♭5", true)],
+            vec![run("C", false), run("maj7", true), run("/E♭", false)],
+            vec![run("Am", false)],
+            vec![run("(G)", false)],
+        ]
+    );
+
+    config.chord_symbols = ChordSymbols::Jazz;
+    let jazz = runs(&config);
+    assert_eq!(jazz[0], [run("F♯", false), run("ø7", true)]);
+    assert_eq!(jazz[1][1], run("Δ7", true));
+    assert_eq!(jazz[2], [run("A−", false)]);
+}
+
+#[test]
+fn test_slash_notation_for_pushes() {
+    let mut config = SvgConfig::default();
+    let chart = parse_chart("C_G F,,<G\n").unwrap();
+    assert!(LayoutEngine::new(&config).layout(&chart)[0].lines[0].slashes.is_empty());
+
+    config.slash_notation = true;
+    let pages = LayoutEngine::new(&config).layout(&chart);
+    let grid = &pages[0].lines[0];
+    // Rows grow by one and a half lines for the notation
+    assert_eq!(grid.baseline, 28.0 + 28.0 + 21.0);
+
+    // Two halves; then the push cuts the held F to a half tied to an eighth, beamed with the
+    // anticipation that is tied over beat four
+    let hollow: Vec<bool> = grid.slashes.iter().map(|slash| slash.hollow).collect();
+    assert_eq!(hollow, [true, true, true, false, false, false]);
+    assert_eq!(grid.ties.len(), 2);
+    assert_eq!(grid.beams.len(), 1);
+    assert_eq!(grid.beams[0].count, 1);
+    assert!(grid.slashes.iter().all(|slash| slash.stem.is_some() && slash.flags == 0));
+}
@@ -1,3 +1,20 @@
+mod backend;
+mod html;
+mod layout;
+mod source_map;
 mod svg;
+mod text;
+#[cfg(feature = "pdf")]
+mod pdf;
 
-pub use svg::{SvgConfig, SvgGenerator};
+pub use backend::{Output, RenderConfig, RenderError, Registry, Renderer};
+pub use html::{HtmlConfig, HtmlGenerator, STYLESHEET};
+pub use layout::{
+    Align, BarlineBox, BarlineKind, BeamBox, ChordCell, ColumnBox, GlyphRun, LayoutEngine, LineBox, PageBox,
+    SlashBox, SourceRef, SymbolRun, TieBox, VoltaBox,
+};
+pub use source_map::{SourceMap, SourceMapEntry};
+pub use svg::{ChordSymbols, FontStyle, LayoutConfig, SvgConfig, SvgGenerator};
+pub use text::{HeaderStyle, TextConfig, TextGenerator};
+#[cfg(feature = "pdf")]
+pub use pdf::PdfGenerator;
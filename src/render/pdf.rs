@@ -0,0 +1,98 @@
+use super::{SvgConfig, SvgGenerator};
+use crate::model::{Chart, Page};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use svg2pdf::usvg::{fontdb, PostProcessingSteps, Tree, TreeParsing, TreePostProc};
+
+/// usvg resolves the SVG's `pt` dimensions at 96 dpi; converting back at the same
+/// resolution keeps PDF pages at the configured size in points
+const DPI: f32 = 96.0;
+
+/// PDF generator: renders charts to SVG and converts them to PDF pages
+pub struct PdfGenerator {
+    svg: SvgGenerator,
+    fonts: fontdb::Database,
+}
+
+impl PdfGenerator {
+    /// Create a new PDF generator with the given configuration, loading system fonts for text
+    pub fn new(config: SvgConfig) -> Self {
+        let mut fonts = fontdb::Database::new();
+        fonts.load_system_fonts();
+        Self {
+            svg: SvgGenerator::new(config),
+            fonts,
+        }
+    }
+
+    /// Create a new PDF generator with default configuration
+    pub fn with_defaults() -> Self {
+        Self::new(SvgConfig::default())
+    }
+
+    /// Render a Chart to PDF bytes, continuing onto further pages when it overflows
+    pub fn render(&self, chart: &Chart) -> Result<Vec<u8>, svg2pdf::usvg::Error> {
+        self.render_pages(&[Page::new(chart.clone())])
+    }
+
+    /// Number of PDF pages a page fills
+    pub fn sheet_count(&self, page: &Page) -> usize {
+        self.svg.sheet_count(page)
+    }
+
+    /// Render pages into one multi-page PDF document, with their overflow on extra pages
+    pub fn render_pages(&self, pages: &[Page]) -> Result<Vec<u8>, svg2pdf::usvg::Error> {
+        let mut pdf = Pdf::new();
+        let catalog_ref = Ref::new(1);
+        let page_tree_ref = Ref::new(2);
+        let mut next_ref = Ref::new(3);
+        let mut page_refs = Vec::with_capacity(pages.len());
+        let svg_name = Name(b"S1");
+
+        for sheet in pages.iter().flat_map(|page| self.svg.render_sheets(page)) {
+            let tree = self.tree(&sheet)?;
+            let page_ref = next_ref;
+            let content_ref = Ref::new(page_ref.get() + 1);
+            let svg_ref = Ref::new(page_ref.get() + 2);
+            next_ref = svg2pdf::convert_tree_into(&tree, options(), &mut pdf, svg_ref);
+
+            // The converted SVG is a unit-square form XObject, scaled up to fill the page
+            let scale = DPI / 72.0;
+            let (width, height) = (tree.size.width() / scale, tree.size.height() / scale);
+
+            let mut pdf_page = pdf.page(page_ref);
+            pdf_page
+                .media_box(Rect::new(0.0, 0.0, width, height))
+                .parent(page_tree_ref)
+                .contents(content_ref);
+            pdf_page.resources().x_objects().pair(svg_name, svg_ref);
+            pdf_page.finish();
+
+            let mut content = Content::new();
+            content
+                .transform([width, 0.0, 0.0, height, 0.0, 0.0])
+                .x_object(svg_name);
+            pdf.stream(content_ref, &content.finish());
+            page_refs.push(page_ref);
+        }
+
+        pdf.catalog(catalog_ref).pages(page_tree_ref);
+        pdf.pages(page_tree_ref)
+            .count(page_refs.len() as i32)
+            .kids(page_refs);
+        Ok(pdf.finish())
+    }
+
+    fn tree(&self, svg: &str) -> Result<Tree, svg2pdf::usvg::Error> {
+        let mut tree = Tree::from_str(svg, &svg2pdf::usvg::Options::default())?;
+        // Text is converted to outlines so the PDF does not depend on installed fonts
+        tree.postprocess(PostProcessingSteps::default(), &self.fonts);
+        Ok(tree)
+    }
+}
+
+fn options() -> svg2pdf::Options {
+    svg2pdf::Options {
+        dpi: DPI,
+        ..svg2pdf::Options::default()
+    }
+}
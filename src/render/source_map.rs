@@ -0,0 +1,83 @@
+// Source maps from rendered text back to chart source
+//
+// Built from pages laid out with `LayoutEngine::layout_source`, so an editor preview can jump
+// from a click on the rendering to the source, or highlight the line being edited.
+
+use super::{Align, PageBox, SourceRef};
+use std::ops::Range;
+
+/// A rendered text box and the source it shows
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceMapEntry {
+    /// Zero-based laid-out page
+    pub page: usize,
+    /// Estimated bounding box in document points, with pages stacked as in `SvgGenerator::render`
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// One-based source line
+    pub line: usize,
+    /// Byte range in the source
+    pub span: Range<usize>,
+}
+
+/// Every text box of a rendering that came from source, in painting order
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceMap {
+    pub entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    /// Collect the boxes of laid-out pages that carry a source reference
+    pub fn from_pages(pages: &[PageBox]) -> Self {
+        let mut entries = Vec::new();
+        for (index, page) in pages.iter().enumerate() {
+            let offset = index as f64 * page.height;
+            for line in &page.lines {
+                let mut push = |x: f64, y: f64, width: f64, height: f64, source: &Option<SourceRef>| {
+                    if let Some(source) = source {
+                        entries.push(SourceMapEntry {
+                            page: index,
+                            x,
+                            y: offset + y,
+                            width,
+                            height,
+                            line: source.line,
+                            span: source.span.clone(),
+                        });
+                    }
+                };
+                // Boxes reach from a font size above the baseline to the bottom of the line height
+                let spacing = line.font.line_height / line.font.size;
+                for cell in &line.cells {
+                    push(cell.x, cell.baseline - cell.size, cell.width, cell.size * spacing, &cell.source);
+                }
+                for column in &line.columns {
+                    let x = match column.align {
+                        Align::Left => column.x,
+                        Align::Center => column.x - column.width / 2.0,
+                        Align::Right => column.x - column.width,
+                    };
+                    let top = line.baseline - line.font.size;
+                    push(x, top, column.width, line.font.line_height, &column.source);
+                }
+            }
+        }
+        Self { entries }
+    }
+
+    /// The entry under a point of the rendered document, for click-to-jump
+    pub fn at(&self, x: f64, y: f64) -> Option<&SourceMapEntry> {
+        self.entries.iter().find(|entry| {
+            (entry.x..=entry.x + entry.width).contains(&x) && (entry.y..=entry.y + entry.height).contains(&y)
+        })
+    }
+
+    /// Entries showing a source line, to highlight the line being edited
+    pub fn line(&self, line: usize) -> impl Iterator<Item = &SourceMapEntry> {
+        self.entries.iter().filter(move |entry| entry.line == line)
+    }
+}
@@ -1,9 +1,16 @@
-use crate::model::{Chart, LineLevel, TextSpan, TextStyle};
-use svg::node::element::{Text as SvgText, TSpan};
+use super::layout::{
+    Align, BarlineBox, BarlineKind, ChordCell, GlyphRun, LayoutEngine, LineBox, PageBox, SlashBox, SourceRef,
+    REPEAT_SIGN_WIDTH, SUPERSCRIPT_RISE, SUPERSCRIPT_SCALE,
+};
+use super::SourceMap;
+use crate::model::{Chart, Page, TextStyle};
+use crate::parser;
+use svg::node::element::{Circle, Line as SvgLine, Path, Text as SvgText, TSpan};
+use svg::node::Blob;
 use svg::Document;
 
 /// Font style configuration (size, weight, line-height)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FontStyle {
     pub size: f64,
     pub weight: String,
@@ -31,7 +38,20 @@ impl Default for LayoutConfig {
     }
 }
 
+/// How chord qualities are written in chord grids
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChordSymbols {
+    /// Spelled out: `Cmaj7`, `Cm7♭5`, `Cdim7`, `Caug`
+    #[default]
+    Spelled,
+    /// Jazz shorthand: `CΔ7`, `Cø7`, `C°7`, `C+`, with `−` for minor
+    Jazz,
+}
+
 /// Configuration for SVG rendering
+///
+/// New settings are added over time, so build configs with `..SvgConfig::default()` to fill in
+/// the fields you don't set.
 #[derive(Debug, Clone)]
 pub struct SvgConfig {
     // Layout
@@ -45,6 +65,15 @@ pub struct SvgConfig {
     pub header2: FontStyle,
     pub header3: FontStyle,
     pub text: FontStyle,
+
+    /// Bars per row of a chord grid; every bar in the grid has the same width
+    pub bars_per_row: usize,
+
+    /// Style of chord symbols in chord grids
+    pub chord_symbols: ChordSymbols,
+
+    /// Draw slash noteheads and rhythms under chord lines that spell out beats, pushes or stabs
+    pub slash_notation: bool,
 }
 
 impl Default for SvgConfig {
@@ -76,10 +105,39 @@ impl Default for SvgConfig {
                 weight: "normal".to_string(),
                 line_height: 14.0,
             },
+
+            bars_per_row: 4,
+            chord_symbols: ChordSymbols::Spelled,
+            slash_notation: false,
         }
     }
 }
 
+/// Stroke width of chord grid barlines, in points
+const BARLINE_WIDTH: f64 = 0.75;
+
+/// Stroke width of the thick line of repeat signs
+const THICK_BARLINE_WIDTH: f64 = 2.0;
+
+/// Radius of repeat sign dots
+const DOT_RADIUS: f64 = 1.2;
+
+/// Gap between a volta's hooks and the barlines below them
+const VOLTA_INSET: f64 = 2.0;
+
+/// Stroke widths of stems and beams
+const STEM_WIDTH: f64 = 0.8;
+const BEAM_WIDTH: f64 = 1.6;
+
+/// Distance between stacked beams and flags
+const BEAM_SPACING: f64 = 2.6;
+
+/// How far ties bow above their ends
+const TIE_BOW: f64 = 2.5;
+
+/// Gap between a notehead and its accent or dot
+const MARK_GAP: f64 = 1.5;
+
 /// SVG generator that renders charts to SVG format
 pub struct SvgGenerator {
     config: SvgConfig,
@@ -96,90 +154,268 @@ impl SvgGenerator {
         Self::new(SvgConfig::default())
     }
 
-    /// Render a Chart to SVG string
+    /// Render a Chart to SVG string; charts longer than a page continue on pages stacked below
     pub fn render(&self, chart: &Chart) -> String {
+        self.paint(&LayoutEngine::new(&self.config).layout(chart))
+    }
+
+    /// Render a page to SVG string, drawing its footer (if any) on the bottom margin
+    pub fn render_page(&self, page: &Page) -> String {
+        self.paint(&LayoutEngine::new(&self.config).layout_page(page))
+    }
+
+    /// Render a page as one SVG document per laid-out page, for paged output
+    pub fn render_sheets(&self, page: &Page) -> Vec<String> {
+        LayoutEngine::new(&self.config)
+            .layout_page(page)
+            .iter()
+            .map(|sheet| self.paint(std::slice::from_ref(sheet)))
+            .collect()
+    }
+
+    /// Number of laid-out pages a page fills, as `render_sheets` would return
+    pub fn sheet_count(&self, page: &Page) -> usize {
+        LayoutEngine::new(&self.config).layout_page(page).len()
+    }
+
+    /// Render chart source with `data-line` and `data-span` (byte range `start-end`) on every
+    /// text element, and a source map of their bounding boxes
+    pub fn render_source(&self, source: &str) -> parser::Result<(String, SourceMap)> {
+        let pages = LayoutEngine::new(&self.config).layout_source(source)?;
+        Ok((self.paint(&pages), SourceMap::from_pages(&pages)))
+    }
+
+    /// Paint laid-out pages into one document, one below the other
+    fn paint(&self, pages: &[PageBox]) -> String {
         let layout = &self.config.layout;
-        
+        let height = layout.height * pages.len() as f64;
+
         let mut document = Document::new()
-            .set("viewBox", format!("0 0 {} {}", layout.width as i32, layout.height as i32))
+            .set("viewBox", format!("0 0 {} {}", layout.width as i32, height as i32))
             .set("width", format!("{}pt", layout.width))
-            .set("height", format!("{}pt", layout.height));
+            .set("height", format!("{}pt", height));
 
-        let mut y = layout.margin_vertical;
+        for (index, page) in pages.iter().enumerate() {
+            let offset = index as f64 * layout.height;
+            for line in &page.lines {
+                document = self.paint_line(document, line, offset);
+            }
+        }
 
-        for line in &chart.lines {
-            y += self.line_height_for_level(line.level);
-            
-            // Left column
-            if !line.left.is_empty() {
-                let text_el = self.render_spans(&line.left, layout.margin_horizontal, y, line.level);
-                document = document.add(text_el);
+        document.to_string()
+    }
+
+    /// Add the text elements for one line, shifted down by `offset`
+    fn paint_line(&self, mut document: Document, line: &LineBox, offset: f64) -> Document {
+        let y = line.baseline + offset;
+
+        for barline in &line.barlines {
+            document = paint_barline(document, barline, offset);
+        }
+
+        for volta in &line.voltas {
+            let (left, right) = (volta.x + VOLTA_INSET, volta.x + volta.width - VOLTA_INSET);
+            let (top, bottom) = (volta.top + offset, volta.bottom + offset);
+            let mut data = format!("M {} {} V {} H {}", left, bottom, top, right);
+            if volta.closed {
+                data.push_str(&format!(" V {}", bottom));
             }
+            document = document.add(
+                Path::new()
+                    .set("d", data)
+                    .set("fill", "none")
+                    .set("stroke", "black")
+                    .set("stroke-width", BARLINE_WIDTH),
+            );
+        }
+
+        for tie in &line.ties {
+            let (y, middle) = (tie.y + offset, (tie.x1 + tie.x2) / 2.0);
+            document = document.add(
+                Path::new()
+                    .set("d", format!("M {} {} Q {} {} {} {}", tie.x1, y, middle, y - 2.0 * TIE_BOW, tie.x2, y))
+                    .set("fill", "none")
+                    .set("stroke", "black")
+                    .set("stroke-width", BARLINE_WIDTH),
+            );
+        }
 
-            // Center column
-            if !line.center.is_empty() {
-                let text_el = self.render_spans(&line.center, layout.width / 2.0, y, line.level)
-                    .set("text-anchor", "middle");
-                document = document.add(text_el);
+        for beam in &line.beams {
+            for index in 0..beam.count {
+                let y = beam.y + offset - index as f64 * BEAM_SPACING;
+                document = document.add(stroke(beam.x1, y, beam.x2, y, BEAM_WIDTH));
             }
+        }
 
-            // Right column
-            if !line.right.is_empty() {
-                let text_el = self.render_spans(
-                    &line.right,
-                    layout.width - layout.margin_horizontal,
-                    y,
-                    line.level,
-                )
-                .set("text-anchor", "end");
-                document = document.add(text_el);
+        for slash in &line.slashes {
+            document = paint_slash(document, slash, offset);
+        }
+
+        for cell in &line.cells {
+            let text_el = self.text_element(cell.x, cell.baseline + offset, cell.size, &line.font.weight, cell.source.as_ref());
+            document = document.add(cell_runs(text_el, cell));
+        }
+
+        for column in &line.columns {
+            let mut text_el = self.text_element(column.x, y, line.font.size, &line.font.weight, column.source.as_ref());
+            text_el = match column.align {
+                Align::Left => text_el,
+                Align::Center => text_el.set("text-anchor", "middle"),
+                Align::Right => text_el.set("text-anchor", "end"),
+            };
+            for run in &column.runs {
+                text_el = text_el.add(glyph_run(run));
             }
+            document = document.add(text_el);
         }
 
-        document.to_string()
+        document
     }
 
-    /// Render a sequence of styled text spans as a single SVG text element with tspans
-    fn render_spans(&self, spans: &[TextSpan], x: f64, y: f64, level: LineLevel) -> SvgText {
-        let style = self.font_style_for_level(level);
-
-        let mut text_el = SvgText::new("")
+    /// An empty text element at (`x`, `y`) in the given font, annotated with its source if known
+    fn text_element(&self, x: f64, y: f64, size: f64, weight: &str, source: Option<&SourceRef>) -> SvgText {
+        let text_el = SvgText::new("")
             .set("x", x)
             .set("y", y)
             .set("font-family", self.config.font_family.as_str())
-            .set("font-size", style.size)
-            .set("font-weight", style.weight.as_str());
-
-        for span in spans {
-            let mut tspan = TSpan::new(&span.text);
-
-            // Apply text styling
-            tspan = match span.style {
-                TextStyle::Normal => tspan,
-                TextStyle::Bold => tspan.set("font-weight", "bold"),
-                TextStyle::Italic => tspan.set("font-style", "italic"),
-                TextStyle::BoldItalic => tspan
-                    .set("font-weight", "bold")
-                    .set("font-style", "italic"),
-            };
+            .set("font-size", size)
+            .set("font-weight", weight);
+        match source {
+            Some(source) => text_el
+                .set("data-line", source.line)
+                .set("data-span", format!("{}-{}", source.span.start, source.span.end)),
+            None => text_el,
+        }
+    }
+}
 
-            text_el = text_el.add(tspan);
+/// Draw a barline; repeat signs add a thick line, then a thin line and dots towards the repeat
+fn paint_barline(mut document: Document, barline: &BarlineBox, offset: f64) -> Document {
+    let (top, bottom) = (barline.top + offset, barline.bottom + offset);
+    let stroke = |x: f64, width: f64| stroke(x, top, x, bottom, width);
+
+    // Sides the repeated bars are on: -1 for the left, 1 for the right
+    let sides: &[f64] = match barline.kind {
+        BarlineKind::Single => return document.add(stroke(barline.x, BARLINE_WIDTH)),
+        BarlineKind::RepeatStart => &[1.0],
+        BarlineKind::RepeatEnd => &[-1.0],
+        BarlineKind::RepeatBoth => &[-1.0, 1.0],
+    };
+    document = document.add(stroke(barline.x, THICK_BARLINE_WIDTH));
+    for side in sides {
+        document = document.add(stroke(barline.x + side * REPEAT_SIGN_WIDTH / 2.0, BARLINE_WIDTH));
+        for fraction in [1.0 / 3.0, 2.0 / 3.0] {
+            document = document.add(
+                Circle::new()
+                    .set("cx", barline.x + side * (REPEAT_SIGN_WIDTH - DOT_RADIUS))
+                    .set("cy", top + (bottom - top) * fraction)
+                    .set("r", DOT_RADIUS),
+            );
         }
+    }
+    document
+}
+
+/// Draw a slash notehead with its stem, flags, dot and accent
+fn paint_slash(mut document: Document, slash: &SlashBox, offset: f64) -> Document {
+    let (x, y) = (slash.x, slash.y + offset);
+    let (half_width, half_height) = (slash.width / 2.0, slash.height / 2.0);
+    let thickness = slash.width * 0.35;
 
-        text_el
+    // A parallelogram rising to the right
+    let head = Path::new().set(
+        "d",
+        format!(
+            "M {} {} H {} L {} {} H {} Z",
+            x - half_width,
+            y + half_height,
+            x - half_width + thickness,
+            x + half_width,
+            y - half_height,
+            x + half_width - thickness,
+        ),
+    );
+    document = document.add(match slash.hollow {
+        true => head.set("fill", "none").set("stroke", "black").set("stroke-width", BARLINE_WIDTH),
+        false => head,
+    });
+
+    let stem_x = x - half_width;
+    if let Some(end) = slash.stem {
+        let end = end + offset;
+        document = document.add(stroke(stem_x, y + half_height, stem_x, end, STEM_WIDTH));
+        for index in 0..slash.flags {
+            let y = end - index as f64 * BEAM_SPACING;
+            document = document.add(stroke(stem_x, y, stem_x + half_width, y - slash.height, BEAM_WIDTH));
+        }
     }
+    if slash.dotted {
+        document = document.add(
+            Circle::new()
+                .set("cx", x + half_width + MARK_GAP)
+                .set("cy", y)
+                .set("r", DOT_RADIUS * 0.75),
+        );
+    }
+    if slash.accent {
+        let bottom = y - half_height - MARK_GAP;
+        let top = bottom - slash.height / 2.0;
+        document = document.add(
+            Path::new()
+                .set(
+                    "d",
+                    format!("M {} {} L {} {} L {} {}", x - half_width, top, x + half_width, (top + bottom) / 2.0, x - half_width, bottom),
+                )
+                .set("fill", "none")
+                .set("stroke", "black")
+                .set("stroke-width", BARLINE_WIDTH),
+        );
+    }
+    document
+}
 
-    fn font_style_for_level(&self, level: LineLevel) -> &FontStyle {
-        match level {
-            LineLevel::Header1 => &self.config.header1,
-            LineLevel::Header2 => &self.config.header2,
-            LineLevel::Header3 => &self.config.header3,
-            LineLevel::Text => &self.config.text,
+/// A straight black line
+fn stroke(x1: f64, y1: f64, x2: f64, y2: f64, width: f64) -> SvgLine {
+    SvgLine::new()
+        .set("x1", x1)
+        .set("y1", y1)
+        .set("x2", x2)
+        .set("y2", y2)
+        .set("stroke", "black")
+        .set("stroke-width", width)
+}
+
+/// Add a cell's runs as tspans; raised runs are smaller and shifted up with `dy`, and the
+/// following run shifts back down to the baseline. The tspans are written without whitespace
+/// between them, which renderers would show as a space inside the chord symbol.
+fn cell_runs(text_el: SvgText, cell: &ChordCell) -> SvgText {
+    let rise = cell.size * SUPERSCRIPT_RISE;
+    let mut raised = false;
+    let mut tspans = String::new();
+    for run in &cell.runs {
+        let mut tspan = TSpan::new(&run.text);
+        if run.raised {
+            tspan = tspan.set("font-size", cell.size * SUPERSCRIPT_SCALE);
         }
+        if run.raised != raised {
+            tspan = tspan.set("dy", if run.raised { -rise } else { rise });
+            raised = run.raised;
+        }
+        tspans.push_str(&tspan.to_string());
     }
+    text_el.add(Blob::new(tspans))
+}
 
-    fn line_height_for_level(&self, level: LineLevel) -> f64 {
-        self.font_style_for_level(level).line_height
+/// A styled run as a tspan
+fn glyph_run(run: &GlyphRun) -> TSpan {
+    let tspan = TSpan::new(&run.text);
+    match run.style {
+        TextStyle::Normal => tspan,
+        TextStyle::Bold => tspan.set("font-weight", "bold"),
+        TextStyle::Italic => tspan.set("font-style", "italic"),
+        TextStyle::BoldItalic => tspan
+            .set("font-weight", "bold")
+            .set("font-style", "italic"),
     }
 }
 
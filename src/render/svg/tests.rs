@@ -1,5 +1,6 @@
 use super::*;
-use crate::model::Line;
+use crate::model::{Line, LineLevel, TextSpan};
+use crate::parser::parse_chart;
 
 #[test]
 fn test_render_empty_chart() {
@@ -18,6 +19,7 @@ fn test_render_single_line() {
         left: vec![TextSpan::plain("Left text")],
         center: vec![],
         right: vec![],
+        chords: vec![],
     }]);
     let generator = SvgGenerator::with_defaults();
     let svg = generator.render(&chart);
@@ -33,6 +35,7 @@ fn test_render_three_columns() {
         left: vec![TextSpan::plain("Left")],
         center: vec![TextSpan::plain("Center")],
         right: vec![TextSpan::plain("Right")],
+        chords: vec![],
     }]);
     let generator = SvgGenerator::with_defaults();
     let svg = generator.render(&chart);
@@ -54,6 +57,7 @@ fn test_render_styled_spans() {
         ],
         center: vec![],
         right: vec![],
+        chords: vec![],
     }]);
     let generator = SvgGenerator::with_defaults();
     let svg = generator.render(&chart);
@@ -70,6 +74,7 @@ fn test_header_styling() {
         left: vec![TextSpan::plain("Title")],
         center: vec![],
         right: vec![],
+        chords: vec![],
     }]);
     let generator = SvgGenerator::with_defaults();
     let svg = generator.render(&chart);
@@ -108,6 +113,7 @@ fn test_custom_config() {
             weight: "normal".to_string(),
             line_height: 18.0,
         },
+        ..SvgConfig::default()
     };
 
     let generator = SvgGenerator::new(config);
@@ -116,8 +122,80 @@ fn test_custom_config() {
         left: vec![TextSpan::plain("Test")],
         center: vec![],
         right: vec![],
+        chords: vec![],
     }]);
 
     let svg = generator.render(&chart);
     assert!(svg.contains("font-size=\"12\""));
 }
+
+#[test]
+fn test_render_page_footer() {
+    let generator = SvgGenerator::with_defaults();
+    let page = Page {
+        chart: Chart::new(vec![]),
+        footer: Some(Line::plain_text(LineLevel::Text, "Setlist", "", "2 / 3")),
+    };
+
+    let svg = generator.render_page(&page);
+    assert!(svg.contains("2 / 3"));
+    // Footer baseline sits on the bottom margin of the default A4 page
+    assert!(svg.contains("y=\"814\""));
+}
+
+#[test]
+fn test_overflow_continues_on_next_page() {
+    let generator = SvgGenerator::with_defaults();
+    let page = Page::new(Chart::new(vec![Line::plain_text(LineLevel::Text, "line", "", ""); 60]));
+
+    let sheets = generator.render_sheets(&page);
+    assert_eq!(sheets.len(), 2);
+    assert_eq!(generator.sheet_count(&page), 2);
+    assert!(sheets[1].contains("viewBox=\"0 0 595 842\""));
+    // A single document stacks the pages
+    assert!(generator.render_page(&page).contains("viewBox=\"0 0 595 1684\""));
+}
+
+#[test]
+fn test_render_source_annotates_text() {
+    let source = "=== <>Song\n\nAm G\n";
+    let (svg, map) = SvgGenerator::with_defaults().render_source(source).unwrap();
+
+    assert!(svg.contains("data-line=\"1\" data-span=\"6-10\""));
+    assert!(svg.contains("data-line=\"3\" data-span=\"15-16\""));
+    // Barlines before, between and after the two bars
+    assert_eq!(svg.matches("<line").count(), 3);
+
+    // Clicking the G chord jumps to its source
+    let g = &map.entries[2];
+    assert_eq!(&source[g.span.clone()], "G");
+    assert_eq!(map.at(g.x + 1.0, g.y + 1.0), Some(g));
+    assert_eq!(map.line(3).count(), 2);
+    // Plain rendering carries no source attributes
+    assert!(!SvgGenerator::with_defaults().render(&parse_chart(source).unwrap()).contains("data-line"));
+}
+
+#[test]
+fn test_chord_qualities_are_superscripts() {
+    let svg = SvgGenerator::with_defaults().render(&parse_chart("Bbmaj7 C\n").unwrap());
+
+    // A raised 7pt quality, with no whitespace to show as a space inside the symbol
+    assert!(svg.contains("<tspan>B♭</tspan><tspan dy=\"-3.5\" font-size=\"7\">maj7</tspan>\n"));
+    assert!(svg.contains("<tspan>C</tspan>"));
+}
+
+#[test]
+fn test_slash_notation_draws_accents_and_ties() {
+    let config = SvgConfig {
+        slash_notation: true,
+        ..SvgConfig::default()
+    };
+    let chart = parse_chart("<>C_D,_<E\n").unwrap();
+    let svg = SvgGenerator::new(config).render(&chart);
+
+    // Four noteheads (C, the held D, the anticipation and E), the accent and the tie into the
+    // push are paths; plain charts draw none
+    assert_eq!(svg.matches("<path").count(), 4 + 1 + 1);
+    assert!(svg.contains(" Q "));
+    assert!(!SvgGenerator::with_defaults().render(&chart).contains("<path"));
+}
@@ -0,0 +1,277 @@
+use crate::model::{Beat, Chart, ChordItem, Line, LineLevel, Measure, Page, TextSpan};
+
+/// Decoration for a header level
+#[derive(Debug, Clone)]
+pub struct HeaderStyle {
+    pub uppercase: bool,
+    /// Character repeated under the header text, if any
+    pub underline: Option<char>,
+}
+
+/// Configuration for plain-text rendering
+#[derive(Debug, Clone)]
+pub struct TextConfig {
+    /// Line width in characters
+    pub width: usize,
+
+    // Decoration per header level
+    pub header1: HeaderStyle,
+    pub header2: HeaderStyle,
+    pub header3: HeaderStyle,
+}
+
+impl Default for TextConfig {
+    fn default() -> Self {
+        Self {
+            width: 72,
+            header1: HeaderStyle {
+                uppercase: true,
+                underline: Some('='),
+            },
+            header2: HeaderStyle {
+                uppercase: false,
+                underline: Some('-'),
+            },
+            header3: HeaderStyle {
+                uppercase: false,
+                underline: None,
+            },
+        }
+    }
+}
+
+/// Text generator that renders charts to fixed-width plain text
+pub struct TextGenerator {
+    config: TextConfig,
+}
+
+/// A bar's content, with the times of the repeat it is in
+struct GridCell {
+    text: String,
+    times: Option<u32>,
+}
+
+impl TextGenerator {
+    /// Create a new text generator with the given configuration
+    pub fn new(config: TextConfig) -> Self {
+        Self { config }
+    }
+
+    /// Create a new text generator with default configuration
+    pub fn with_defaults() -> Self {
+        Self::new(TextConfig::default())
+    }
+
+    /// Render a Chart to plain text, one output line per row
+    pub fn render(&self, chart: &Chart) -> String {
+        let grids: Vec<(Vec<GridCell>, Vec<String>)> = chart
+            .lines
+            .iter()
+            .zip(chart.line_measures())
+            .map(|(line, measures)| {
+                let cells = grid_cells(&line.chords);
+                let barlines = barlines(&cells, &measures);
+                (cells, barlines)
+            })
+            .collect();
+
+        // Every grid cell and barline in the chart shares one width so bars line up
+        let cell_width = grids
+            .iter()
+            .flat_map(|(cells, _)| cells)
+            .map(|cell| cell.text.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        let barline_width = grids
+            .iter()
+            .flat_map(|(_, barlines)| barlines)
+            .map(|barline| barline.chars().count())
+            .max()
+            .unwrap_or(1);
+
+        let mut output = String::new();
+        for (line, (cells, barlines)) in chart.lines.iter().zip(&grids) {
+            let rows = if line.level == LineLevel::Chords {
+                self.render_grid(cells, barlines, cell_width, barline_width)
+            } else {
+                self.render_line(line)
+            };
+            for row in rows {
+                output.push_str(&row);
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    /// Render a page to plain text, with its footer (if any) after a blank line
+    pub fn render_page(&self, page: &Page) -> String {
+        let mut output = self.render(&page.chart);
+        if let Some(footer) = &page.footer {
+            output.push('\n');
+            for row in self.render_line(footer) {
+                output.push_str(&row);
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    /// Three-column text line, plus an underline row for decorated headers
+    fn render_line(&self, line: &Line) -> Vec<String> {
+        let style = match line.level {
+            LineLevel::Header1 => Some(&self.config.header1),
+            LineLevel::Header2 => Some(&self.config.header2),
+            LineLevel::Header3 => Some(&self.config.header3),
+            LineLevel::Text | LineLevel::Chords => None,
+        };
+        let text = |spans: &[TextSpan]| {
+            let text = TextSpan::join(spans);
+            match style {
+                Some(style) if style.uppercase => text.to_uppercase(),
+                _ => text,
+            }
+        };
+
+        let width = self.config.width;
+        let mut row: Vec<char> = Vec::new();
+        let mut placed = Vec::new();
+        let mut place = |text: String, start: usize| {
+            if text.is_empty() {
+                return;
+            }
+            // Never overlap the previous column
+            let start = if row.is_empty() { start } else { start.max(row.len() + 1) };
+            let length = text.chars().count();
+            row.resize(start, ' ');
+            row.extend(text.chars());
+            placed.push((start, length));
+        };
+
+        let (left, center, right) = (text(&line.left), text(&line.center), text(&line.right));
+        let center_length = center.chars().count();
+        let right_length = right.chars().count();
+        place(left, 0);
+        place(center, width.saturating_sub(center_length) / 2);
+        place(right, width.saturating_sub(right_length));
+
+        let mut rows = vec![row.into_iter().collect::<String>()];
+        if let Some(underline) = style.and_then(|style| style.underline) {
+            if !placed.is_empty() {
+                let mut decoration = String::new();
+                for (start, length) in placed {
+                    decoration.push_str(&" ".repeat(start - decoration.chars().count()));
+                    decoration.extend(std::iter::repeat_n(underline, length));
+                }
+                rows.push(decoration);
+            }
+        }
+        rows
+    }
+
+    /// Chord line as a grid of fixed-width cells between padded barlines, wrapped to the width
+    fn render_grid(&self, cells: &[GridCell], barlines: &[String], cell_width: usize, barline_width: usize) -> Vec<String> {
+        let barline = |index: usize| format!("{:<width$}", barlines[index], width = barline_width);
+        let mut rows = Vec::new();
+        let mut row = String::new();
+
+        for (index, cell) in cells.iter().enumerate() {
+            let cell = format!(" {:<width$} ", cell.text, width = cell_width);
+            // Leave room for the closing barline
+            let needed = row.chars().count() + 2 * barline_width + cell.chars().count();
+            if !row.is_empty() && needed > self.config.width {
+                // A repeat sign on the break ends one row and starts the next
+                rows.push(format!("{}{}", row, closing(&barlines[index])));
+                row = format!("{:<width$}", opening(&barlines[index]), width = barline_width);
+            } else {
+                row.push_str(&barline(index));
+            }
+            row.push_str(&cell);
+        }
+        if !row.is_empty() {
+            rows.push(format!("{}{}", row, barlines[cells.len()]));
+        }
+        rows
+    }
+}
+
+/// Flatten chord items into cells, with annotations and ending numbers before the bar they mark
+fn grid_cells(items: &[ChordItem]) -> Vec<GridCell> {
+    let mut cells = Vec::new();
+    let mut prefix = String::new();
+    push_cells(items, None, &mut cells, &mut prefix);
+    cells
+}
+
+fn push_cells(items: &[ChordItem], times: Option<u32>, cells: &mut Vec<GridCell>, prefix: &mut String) {
+    for item in items {
+        match item {
+            ChordItem::Bar(bar) => {
+                let beats: Vec<String> = bar
+                    .beats
+                    .iter()
+                    .map(|beat| match beat {
+                        Beat::Empty => "/".to_string(),
+                        beat => beat.to_string(),
+                    })
+                    .collect();
+                let hold = if bar.fermata { " (hold)" } else { "" };
+                push_cell(cells, prefix, format!("{}{}", beats.join(" "), hold), times);
+            }
+            ChordItem::RepeatBar => push_cell(cells, prefix, "%".to_string(), times),
+            ChordItem::Group(group) => push_cells(&group.items, group.times, cells, prefix),
+            ChordItem::Ending(number) => prefix.push_str(&format!("{}. ", number)),
+            ChordItem::Annotation(text) => prefix.push_str(&format!("\"{}\" ", text)),
+        }
+    }
+}
+
+fn push_cell(cells: &mut Vec<GridCell>, prefix: &mut String, text: String, times: Option<u32>) {
+    cells.push(GridCell {
+        text: format!("{}{}", std::mem::take(prefix), text),
+        times,
+    });
+}
+
+/// Barlines before each cell and after the last: `|`, `|:`, `:|`, `:|:`, or `:|x3` when the
+/// repeat count was written out. Repeats close where the chart's measures do, before the last
+/// ending.
+fn barlines(cells: &[GridCell], measures: &[Measure]) -> Vec<String> {
+    (0..=cells.len())
+        .map(|index| {
+            let close = index
+                .checked_sub(1)
+                .filter(|&previous| measures.get(previous).is_some_and(|measure| measure.repeat_end.is_some()))
+                .map(|previous| match cells[previous].times {
+                    Some(times) => format!(":|x{}", times),
+                    None => ":|".to_string(),
+                });
+            let open = measures.get(index).is_some_and(|measure| measure.repeat_start);
+            match (close, open) {
+                (None, false) => "|".to_string(),
+                (None, true) => "|:".to_string(),
+                (Some(close), false) => close,
+                (Some(close), true) if close == ":|" => ":|:".to_string(),
+                (Some(close), true) => format!("{}|:", close),
+            }
+        })
+        .collect()
+}
+
+/// The part of a barline that closes a row
+fn closing(barline: &str) -> &str {
+    match barline {
+        "|:" => "|",
+        ":|:" => ":|",
+        barline => barline.strip_suffix("|:").unwrap_or(barline),
+    }
+}
+
+/// The part of a barline that opens a row
+fn opening(barline: &str) -> &str {
+    if barline.ends_with(':') { "|:" } else { "|" }
+}
+
+#[cfg(test)]
+mod tests;
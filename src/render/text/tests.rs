@@ -0,0 +1,56 @@
+use super::*;
+use crate::parser::parse_chart;
+
+fn narrow(width: usize) -> TextGenerator {
+    TextGenerator::new(TextConfig {
+        width,
+        ..TextConfig::default()
+    })
+}
+
+#[test]
+fn test_render_columns_and_headers() {
+    let chart = parse_chart("=== <>Song\n= <Intro >*Swing*\n- <>Soft\n").unwrap();
+    let text = narrow(20).render(&chart);
+
+    assert_eq!(
+        text,
+        "        SONG\n        ====\nIntro          Swing\n        Soft\n"
+    );
+}
+
+#[test]
+fn test_columns_never_overlap() {
+    let chart = parse_chart("= <A long left column >Right\n").unwrap();
+    let text = narrow(16).render(&chart);
+    assert_eq!(text, "A long left column Right\n");
+}
+
+#[test]
+fn test_render_chord_grid() {
+    let chart = parse_chart("Am_G % (F 1. E 2. Em_, fermata) 2x\n").unwrap();
+    let text = narrow(120).render(&chart);
+    assert_eq!(
+        text,
+        "|    Am G           |    %              |:   F              |    1. E           :|x2 2. Em / (hold) |\n"
+    );
+}
+
+#[test]
+fn test_repeat_barlines_align_across_rows() {
+    let chart = parse_chart("(C F) D
+G A E
+").unwrap();
+    let text = narrow(30).render(&chart);
+    assert_eq!(
+        text,
+        "|: C    |  F    :| D    |\n|  G    |  A    |  E    |\n"
+    );
+}
+
+#[test]
+fn test_chord_grid_wraps_to_width() {
+    let chart = parse_chart("C D E F G A\n").unwrap();
+    let text = narrow(22).render(&chart);
+    assert_eq!(text, "| C    | D    | E    |\n| F    | G    | A    |\n");
+}
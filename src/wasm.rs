@@ -0,0 +1,173 @@
+// WebAssembly bindings for browser editors
+//
+// Build with `wasm-pack build --target web -- --features wasm`. Every function takes chart
+// source and throws a `Diagnostic` object on parse errors, so editors can underline the
+// offending range without parsing miette reports.
+
+use crate::parser::{self, ParseError};
+use crate::render::SvgGenerator;
+use wasm_bindgen::prelude::*;
+
+/// A parse error with its location, ready to show in an editor
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// What the parser expected at this point
+    pub help: String,
+    /// Start of the offending range, in UTF-16 code units as used by JavaScript strings
+    pub start: usize,
+    /// End of the offending range, in UTF-16 code units
+    pub end: usize,
+    /// One-based line of the start
+    pub line: usize,
+    /// One-based column of the start, in characters
+    pub column: usize,
+}
+
+impl Diagnostic {
+    fn new(source: &str, error: &ParseError) -> Self {
+        let span = error.span();
+        let (line, column) = error.line_col();
+        let offsets = Utf16Offsets::new(source);
+        Self {
+            message: error.to_string(),
+            help: error.help().to_string(),
+            start: offsets.get(span.start),
+            end: offsets.get(span.end),
+            line,
+            column,
+        }
+    }
+}
+
+/// Parse a chart into a plain object following `docs/chart.schema.json`; throws an `Error`
+/// if the chart cannot be written as a document
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<JsValue, JsValue> {
+    let chart = parser::parse_chart(source).map_err(|error| Diagnostic::new(source, &error))?;
+    let json = chart.to_json().map_err(|error| JsError::new(&error.to_string()))?;
+    js_sys::JSON::parse(&json)
+}
+
+/// Render a chart to an SVG document string
+#[wasm_bindgen(js_name = renderSvg)]
+pub fn render_svg(source: &str) -> Result<String, Diagnostic> {
+    let chart = parser::parse_chart(source).map_err(|error| Diagnostic::new(source, &error))?;
+    Ok(SvgGenerator::with_defaults().render(&chart))
+}
+
+/// Render a chart to `{ svg, map }`: the SVG with `data-line`/`data-span` attributes, and a list
+/// of `{ page, x, y, width, height, line, start, end }` text boxes. Spans in both are UTF-16
+/// ranges, so they index JavaScript strings directly.
+#[wasm_bindgen(js_name = renderSvgWithSourceMap)]
+pub fn render_svg_with_source_map(source: &str) -> Result<JsValue, JsValue> {
+    let (svg, map) = SvgGenerator::with_defaults()
+        .render_source(source)
+        .map_err(|error| Diagnostic::new(source, &error))?;
+    let offsets = Utf16Offsets::new(source);
+    let svg = utf16_spans(&svg, &offsets);
+    let entries: Vec<serde_json::Value> = map
+        .entries
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "page": entry.page,
+                "x": entry.x,
+                "y": entry.y,
+                "width": entry.width,
+                "height": entry.height,
+                "line": entry.line,
+                "start": offsets.get(entry.span.start),
+                "end": offsets.get(entry.span.end),
+            })
+        })
+        .collect();
+    let document = serde_json::json!({ "svg": svg, "map": entries });
+    js_sys::JSON::parse(&document.to_string())
+}
+
+/// Reformat chart source in the canonical style
+#[wasm_bindgen]
+pub fn format(source: &str) -> Result<String, Diagnostic> {
+    parser::format(source).map_err(|error| Diagnostic::new(source, &error))
+}
+
+/// UTF-16 offset of every byte offset into a source, computed in one pass
+struct Utf16Offsets(Vec<usize>);
+
+impl Utf16Offsets {
+    fn new(source: &str) -> Self {
+        let mut offsets = Vec::with_capacity(source.len() + 1);
+        let mut units = 0;
+        for character in source.chars() {
+            // Bytes inside a character map to its start
+            offsets.extend(std::iter::repeat_n(units, character.len_utf8()));
+            units += character.len_utf16();
+        }
+        offsets.push(units);
+        Self(offsets)
+    }
+
+    /// UTF-16 offset of a byte offset, clamped to the end of the source
+    fn get(&self, offset: usize) -> usize {
+        self.0[offset.min(self.0.len() - 1)]
+    }
+}
+
+/// Rewrite the byte ranges of `data-span` attributes as UTF-16 ranges
+fn utf16_spans(svg: &str, offsets: &Utf16Offsets) -> String {
+    const ATTRIBUTE: &str = "data-span=\"";
+    let mut output = String::with_capacity(svg.len());
+    let mut rest = svg;
+    while let Some(index) = rest.find(ATTRIBUTE) {
+        let (before, after) = rest.split_at(index + ATTRIBUTE.len());
+        output.push_str(before);
+        let end = after.find('"').unwrap_or(after.len());
+        match after[..end].split_once('-').and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?))) {
+            Some((start, end)) => output.push_str(&format!("{}-{}", offsets.get(start), offsets.get(end))),
+            None => output.push_str(&after[..end]),
+        }
+        rest = &after[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_offsets_are_utf16() {
+        let source = "= <>2011 • 𝄞\n(Am G\n";
+        let diagnostic = render_svg(source).unwrap_err();
+        let error = parser::parse_chart(source).unwrap_err();
+
+        assert_eq!(diagnostic.message, "Parse error");
+        assert_eq!(diagnostic.help, error.help());
+        assert_eq!((diagnostic.line, diagnostic.column), error.line_col());
+        // `•` is one UTF-16 unit but three bytes, `𝄞` two units but four bytes
+        assert_eq!(diagnostic.start, error.span().start - 4);
+        assert_eq!(Utf16Offsets::new("•𝄞x").get("•𝄞".len()), 3);
+    }
+
+    #[test]
+    fn test_svg_spans_are_utf16() {
+        let source = "= <>2011 • 𝄞\nAm G\n";
+        let offsets = Utf16Offsets::new(source);
+        let (svg, _) = SvgGenerator::with_defaults().render_source(source).unwrap();
+        let am = source.find("Am").unwrap();
+        assert!(svg.contains(&format!("data-span=\"{}-{}\"", am, am + 2)));
+
+        // `Am` starts 4 UTF-16 units before its byte offset
+        let svg = utf16_spans(&svg, &offsets);
+        assert!(svg.contains(&format!("data-span=\"{}-{}\"", am - 4, am - 2)));
+    }
+
+    #[test]
+    fn test_render_and_format() {
+        assert!(render_svg("=== <>Song\nC\n").unwrap().starts_with("<svg"));
+        assert_eq!(format("=  <>Song\nC   G\n").unwrap(), "= <>Song\nC G\n");
+    }
+}